@@ -0,0 +1,49 @@
+#!/usr/bin/env python3
+"""Regenerate src/models/checksums.rs from the Git LFS metadata of the published models.
+
+Hugging Face stores every model file of ggerganov/whisper.cpp in Git LFS,
+whose object id is the SHA-256 of the file.
+
+Run from the repository root: `python3 scripts/update-model-checksums.py`
+"""
+
+import json
+import re
+import urllib.request
+
+REPO = "ggerganov/whisper.cpp"
+API = f"https://huggingface.co/api/models/{REPO}/tree/main"
+OUTPUT = "src/models/checksums.rs"
+
+
+def catalog_names():
+    with open("src/models/mod.rs") as f:
+        return re.findall(r'^\s*model\("([^"]+)"', f.read(), re.M)
+
+
+def main():
+    with urllib.request.urlopen(API) as response:
+        files = {entry["path"]: entry for entry in json.load(response)}
+
+    lines = []
+    for name in catalog_names():
+        entry = files.get(f"ggml-{name}.bin")
+        if entry is None or "lfs" not in entry:
+            raise SystemExit(f"ggml-{name}.bin is not published in {REPO}")
+        lfs = entry["lfs"]
+        lines.append(f'    ("{name}", {lfs["size"]}, "{lfs["oid"]}"),')
+
+    with open(OUTPUT, "w") as f:
+        f.write(
+            f"// Generated by scripts/update-model-checksums.py from the Git LFS metadata of\n"
+            f"// https://huggingface.co/{REPO}, do not edit by hand.\n"
+            "\n"
+            "/// Size in bytes and SHA-256 of each published model file, by model name.\n"
+            "pub(super) const CHECKSUMS: &[(&str, u64, &str)] = &[\n"
+            + "\n".join(lines)
+            + "\n];\n"
+        )
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,368 @@
+//! Decoding audio files into samples ready for [crate::WhisperState::full].
+//!
+//! Requires the `audio-decode` feature.
+//! WAV, FLAC, MP3 and Ogg/Vorbis are decoded in pure Rust.
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::Duration;
+
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::{MediaSource, MediaSourceStream};
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+use crate::common_logging::generic_error;
+use crate::{downmix, resample_to_whisper, DownmixStrategy, WhisperError};
+
+/// Decoded audio, converted to 16 kHz mono f32 samples.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WhisperAudio {
+    /// Mono samples at [crate::WHISPER_SAMPLE_RATE], ready to pass to [crate::WhisperState::full].
+    pub samples: Vec<f32>,
+    /// Duration of the original audio.
+    pub duration: Duration,
+    /// Sample rate of the original audio, in Hz.
+    pub sample_rate: u32,
+    /// Number of channels in the original audio.
+    pub channels: usize,
+    /// Positions of the original channels, as a `WAVEFORMATEXTENSIBLE` channel mask
+    /// (bit 0 is front left, bit 1 front right, bit 2 front centre, and so on).
+    /// 0 if the file does not specify them.
+    pub channel_mask: u32,
+}
+
+/// Convert a symphonia error, logging the details if a logging backend is enabled.
+fn audio_error(e: SymphoniaError) -> WhisperError {
+    generic_error!("failed to decode audio: {}", e);
+    match e {
+        SymphoniaError::IoError(e) => WhisperError::FailedToReadAudio(e.kind()),
+        SymphoniaError::Unsupported(_) => WhisperError::UnsupportedAudioFormat,
+        _ => WhisperError::FailedToDecodeAudio,
+    }
+}
+
+fn read_error(e: io::Error) -> WhisperError {
+    generic_error!("failed to read audio: {}", e);
+    WhisperError::FailedToReadAudio(e.kind())
+}
+
+/// Decode an audio file into 16 kHz mono samples.
+///
+/// The format is detected from the contents, using the file extension as a hint.
+///
+/// # Errors
+/// * [WhisperError::FailedToReadAudio] if reading the file failed.
+/// * [WhisperError::UnsupportedAudioFormat] if the format or codec is not supported.
+/// * [WhisperError::FailedToDecodeAudio] if the file is malformed.
+/// * [WhisperError::NoAudioTrack] if the file contains no decodable audio track.
+/// * [WhisperError::SampleRateChanged] if the sample rate changes partway through the audio.
+///
+/// # Examples
+/// ```no_run
+/// # fn main() -> Result<(), whisper_rs::WhisperError> {
+/// let audio = whisper_rs::audio::load_file("audio.flac")?;
+/// println!(
+///     "{} channels at {} Hz, {:?}",
+///     audio.channels, audio.sample_rate, audio.duration
+/// );
+/// # Ok(())
+/// # }
+/// ```
+pub fn load_file<P: AsRef<Path>>(path: P) -> Result<WhisperAudio, WhisperError> {
+    let path = path.as_ref();
+    let extension = path.extension().and_then(|e| e.to_str());
+    decode(File::open(path).map_err(read_error)?, extension)
+}
+
+/// Decode audio from a reader into 16 kHz mono samples.
+///
+/// # Arguments
+/// * `reader` - Source of the encoded audio, e.g. a `File` or an `io::Cursor` over a buffer.
+/// * `extension` - File extension to use as a hint when detecting the format, e.g. `"mp3"`.
+///
+/// # Errors
+/// Same as [load_file].
+pub fn decode<R>(reader: R, extension: Option<&str>) -> Result<WhisperAudio, WhisperError>
+where
+    R: Read + Seek + Send + Sync + 'static,
+{
+    let stream = MediaSourceStream::new(Box::new(Source::new(reader)), Default::default());
+    let mut hint = Hint::new();
+    if let Some(extension) = extension {
+        hint.with_extension(extension);
+    }
+    let probed = symphonia::default::get_probe()
+        .format(
+            &hint,
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(audio_error)?;
+    let mut format = probed.format;
+
+    let track = format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or(WhisperError::NoAudioTrack)?;
+    let track_id = track.id;
+    let mut sample_rate = track.codec_params.sample_rate;
+    let mut channels = track.codec_params.channels;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(audio_error)?;
+
+    let mut interleaved = Vec::new();
+    let mut buffer: Option<SampleBuffer<f32>> = None;
+    let mut decoded_rate = None;
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            // symphonia reports the end of the stream as an unexpected EOF
+            Err(SymphoniaError::IoError(e)) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(audio_error(e)),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // a corrupt packet only loses that packet, keep going like other players do
+            Err(SymphoniaError::DecodeError(_)) => continue,
+            Err(e) => return Err(audio_error(e)),
+        };
+
+        let spec = *decoded.spec();
+        check_sample_rate(&mut decoded_rate, spec.rate)?;
+        sample_rate = Some(spec.rate);
+        channels = Some(spec.channels);
+        let needed = decoded.capacity() * spec.channels.count();
+        let buffer = match &mut buffer {
+            Some(buffer) if buffer.capacity() >= needed => buffer,
+            buffer => buffer.insert(SampleBuffer::new(decoded.capacity() as u64, spec)),
+        };
+        buffer.copy_interleaved_ref(decoded);
+        interleaved.extend_from_slice(buffer.samples());
+    }
+
+    let sample_rate = sample_rate.ok_or(WhisperError::NoAudioTrack)?;
+    let channel_mask = channels.map_or(0, |c| c.bits());
+    let n_channels = channels.map_or(1, |c| c.count()).max(1);
+    let frames = interleaved.len() / n_channels;
+    interleaved.truncate(frames * n_channels);
+
+    let mono = match n_channels {
+        1 => interleaved,
+        n => downmix(&interleaved, n, &DownmixStrategy::Average)
+            .expect("interleaved samples were truncated to whole frames"),
+    };
+
+    Ok(WhisperAudio {
+        samples: resample_to_whisper(&mono, sample_rate),
+        duration: Duration::from_secs_f64(frames as f64 / sample_rate as f64),
+        sample_rate,
+        channels: n_channels,
+        channel_mask,
+    })
+}
+
+/// Record the sample rate of a decoded packet, failing if it differs from the previous packets.
+fn check_sample_rate(previous: &mut Option<u32>, rate: u32) -> Result<(), WhisperError> {
+    match *previous {
+        // resampling at either rate would play part of the audio at the wrong speed
+        Some(from) if from != rate => Err(WhisperError::SampleRateChanged { from, to: rate }),
+        _ => {
+            *previous = Some(rate);
+            Ok(())
+        }
+    }
+}
+
+/// Adapter to hand any seekable reader to symphonia.
+struct Source<R> {
+    reader: R,
+    /// Length of the input in bytes, if it could be determined.
+    len: Option<u64>,
+}
+
+impl<R: Seek> Source<R> {
+    fn new(mut reader: R) -> Self {
+        // knowing the length lets symphonia seek to the end for trailing metadata and durations
+        let len = reader.stream_position().and_then(|position| {
+            let len = reader.seek(SeekFrom::End(0))?;
+            reader.seek(SeekFrom::Start(position))?;
+            Ok(len)
+        });
+        Self {
+            reader,
+            len: len.ok(),
+        }
+    }
+}
+
+impl<R: Read> Read for Source<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reader.read(buf)
+    }
+}
+
+impl<R: Seek> Seek for Source<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.reader.seek(pos)
+    }
+}
+
+impl<R: Read + Seek + Send + Sync> MediaSource for Source<R> {
+    fn is_seekable(&self) -> bool {
+        true
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        self.len
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::{load_sample, SAMPLE_PATH};
+    use std::io::Cursor;
+
+    fn wav(channels: u16, sample_rate: u32, frames: usize) -> Cursor<Vec<u8>> {
+        let spec = hound::WavSpec {
+            channels,
+            sample_rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut cursor = Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(&mut cursor, spec).unwrap();
+        for i in 0..frames {
+            let t = i as f64 / sample_rate as f64;
+            for c in 0..channels {
+                // each channel gets its own constant offset on top of a 440 Hz tone
+                let x = 0.25 * (2.0 * std::f64::consts::PI * 440.0 * t).sin() + 0.1 * c as f64;
+                writer.write_sample((x * 32767.0) as i16).unwrap();
+            }
+        }
+        writer.finalize().unwrap();
+        cursor.set_position(0);
+        cursor
+    }
+
+    #[test]
+    fn test_decode_stereo_wav() {
+        let audio = decode(wav(2, 44100, 44100), Some("wav")).unwrap();
+        assert_eq!(audio.sample_rate, 44100);
+        assert_eq!(audio.channels, 2);
+        assert_eq!(audio.channel_mask, 0b11);
+        assert_eq!(audio.duration, Duration::from_secs(1));
+        assert_eq!(audio.samples.len(), 16000);
+        // the mean of the downmix is the mean of the channel offsets
+        let mean = audio.samples.iter().sum::<f32>() / audio.samples.len() as f32;
+        assert!((mean - 0.05).abs() < 1e-3, "{}", mean);
+    }
+
+    #[test]
+    fn test_decode_multichannel_wav() {
+        let audio = decode(wav(4, 16000, 8000), None).unwrap();
+        assert_eq!(audio.channels, 4);
+        assert_eq!(audio.duration, Duration::from_millis(500));
+        assert_eq!(audio.samples.len(), 8000);
+        let mean = audio.samples.iter().sum::<f32>() / audio.samples.len() as f32;
+        assert!((mean - 0.15).abs() < 1e-3, "{}", mean);
+    }
+
+    #[test]
+    fn test_load_file_matches_hound() {
+        let audio = load_file(SAMPLE_PATH).unwrap();
+        let expected = load_sample();
+        assert_eq!(audio.sample_rate, 16000);
+        assert_eq!(audio.channels, 1);
+        assert_eq!(audio.samples.len(), expected.len());
+        for (a, b) in audio.samples.iter().zip(&expected) {
+            assert!((a - b).abs() < 1e-4);
+        }
+    }
+
+    // The fixtures are written by `tests/fixtures/generate.py`.
+
+    #[test]
+    fn test_load_flac_matches_wav() {
+        let audio = load_file("./tests/fixtures/tone.flac").unwrap();
+        assert_eq!(audio.sample_rate, 8000);
+        assert_eq!(audio.channels, 2);
+        assert_eq!(audio.duration, Duration::from_millis(250));
+        // same samples as the equivalent WAV file, FLAC is lossless
+        let expected = decode(wav(2, 8000, 2000), Some("wav")).unwrap();
+        assert_eq!(audio.samples.len(), expected.samples.len());
+        for (a, b) in audio.samples.iter().zip(&expected.samples) {
+            assert!((a - b).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_load_mp3() {
+        let audio = load_file("./tests/fixtures/silence.mp3").unwrap();
+        assert_eq!(audio.sample_rate, 48000);
+        assert_eq!(audio.channels, 1);
+        // 10 frames of 1152 samples
+        assert_eq!(audio.duration, Duration::from_millis(240));
+        assert_eq!(audio.samples.len(), 3840);
+        assert!(audio.samples.iter().all(|&x| x == 0.0));
+    }
+
+    #[test]
+    fn test_load_ogg_vorbis() {
+        let audio = load_file("./tests/fixtures/silence.ogg").unwrap();
+        assert_eq!(audio.sample_rate, 8000);
+        assert_eq!(audio.channels, 1);
+        // 40 blocks of 128 samples after the first
+        assert_eq!(audio.duration, Duration::from_millis(640));
+        assert_eq!(audio.samples.len(), 10240);
+        assert!(audio.samples.iter().all(|&x| x == 0.0));
+    }
+
+    #[test]
+    fn test_sample_rate_change() {
+        let mut rate = None;
+        assert!(check_sample_rate(&mut rate, 44100).is_ok());
+        assert!(check_sample_rate(&mut rate, 44100).is_ok());
+        assert!(matches!(
+            check_sample_rate(&mut rate, 48000),
+            Err(WhisperError::SampleRateChanged {
+                from: 44100,
+                to: 48000
+            })
+        ));
+    }
+
+    #[test]
+    fn test_byte_len() {
+        let mut reader = wav(1, 16000, 100);
+        let len = reader.get_ref().len() as u64;
+        reader.set_position(10);
+        let source = Source::new(reader);
+        assert_eq!(source.byte_len(), Some(len));
+        assert_eq!(source.reader.position(), 10);
+    }
+
+    #[test]
+    fn test_decode_garbage() {
+        let garbage = Cursor::new(vec![0x42u8; 4096]);
+        assert!(matches!(
+            decode(garbage, None),
+            Err(WhisperError::UnsupportedAudioFormat)
+        ));
+        assert!(matches!(
+            load_file("./does/not/exist.wav"),
+            Err(WhisperError::FailedToReadAudio(io::ErrorKind::NotFound))
+        ));
+    }
+}
@@ -0,0 +1,599 @@
+//! Transcription of long audio in parallel chunks split at silence.
+
+use std::ffi::c_int;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use crate::{FullParams, Segment, Transcript, WhisperContext, WhisperError, WHISPER_SAMPLE_RATE};
+
+/// Configuration of [ChunkedTranscriber].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkingConfig {
+    /// Chunks are at least this long, in milliseconds, except for the last one.
+    ///
+    /// Defaults to 30000.
+    pub min_chunk_ms: u32,
+    /// Chunks are at most this long, in milliseconds, not counting the overlap.
+    /// Each chunk is cut at the quietest point between the minimum and maximum length.
+    ///
+    /// Defaults to 60000.
+    pub max_chunk_ms: u32,
+    /// Audio from the neighbouring chunks added on both sides of each chunk, in milliseconds,
+    /// so words at a cut that did not fall into silence are still heard whole.
+    /// Segments transcribed twice because of the overlap are only kept once.
+    ///
+    /// Defaults to 1000.
+    pub overlap_ms: u32,
+    /// Number of chunks transcribed at the same time, each with its own [crate::WhisperState].
+    /// Every worker uses [FullParams::set_n_threads] threads and holds its own state in memory.
+    ///
+    /// Defaults to the available parallelism divided by 4, but at least 1.
+    pub n_workers: usize,
+}
+
+impl Default for ChunkingConfig {
+    fn default() -> Self {
+        let parallelism = std::thread::available_parallelism().map_or(1, |n| n.get());
+        Self {
+            min_chunk_ms: 30000,
+            max_chunk_ms: 60000,
+            overlap_ms: 1000,
+            n_workers: (parallelism / 4).max(1),
+        }
+    }
+}
+
+/// A chunk of audio to transcribe, in samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Chunk {
+    /// Audio passed to the model, including the overlap.
+    start: usize,
+    end: usize,
+    /// Part of the audio this chunk is responsible for.
+    /// Owned ranges of all chunks are adjacent and cover the whole input.
+    own_start: usize,
+    own_end: usize,
+}
+
+/// Length of the frames the energy of the audio is measured over when looking for a cut.
+const FRAME_LEN: usize = WHISPER_SAMPLE_RATE as usize / 50;
+/// Number of frames averaged when looking for a cut, so a single quiet frame
+/// in the middle of a word is not mistaken for a pause.
+const CUT_FRAMES: usize = 10;
+
+fn ms_to_samples(ms: u32) -> usize {
+    (ms as u64 * WHISPER_SAMPLE_RATE as u64 / 1000) as usize
+}
+
+/// Split `samples` into chunks, cutting at the quietest point within the allowed lengths.
+fn plan_chunks(samples: &[f32], config: &ChunkingConfig) -> Vec<Chunk> {
+    let max_len = ms_to_samples(config.max_chunk_ms).max(FRAME_LEN * CUT_FRAMES);
+    let min_len = ms_to_samples(config.min_chunk_ms).min(max_len);
+    let overlap = ms_to_samples(config.overlap_ms);
+
+    let energy = samples
+        .chunks(FRAME_LEN)
+        .map(|frame| frame.iter().map(|x| x * x).sum::<f32>())
+        .collect::<Vec<_>>();
+
+    let mut cuts = vec![0];
+    let mut pos = 0;
+    while samples.len() - pos > max_len {
+        // search whole windows of frames starting between the minimum and maximum length
+        let first = (pos + min_len).div_ceil(FRAME_LEN);
+        let last = ((pos + max_len) / FRAME_LEN)
+            .saturating_sub(CUT_FRAMES)
+            .max(first);
+        let quietest = (first..=last)
+            .min_by(|&a, &b| {
+                let sum = |i: usize| {
+                    energy[i..(i + CUT_FRAMES).min(energy.len())]
+                        .iter()
+                        .sum::<f32>()
+                };
+                sum(a).total_cmp(&sum(b))
+            })
+            .unwrap_or(first);
+        // cut in the middle of the quietest window
+        pos = ((quietest + CUT_FRAMES / 2) * FRAME_LEN).clamp(pos + min_len, pos + max_len);
+        cuts.push(pos);
+    }
+    cuts.push(samples.len());
+
+    cuts.windows(2)
+        .map(|w| Chunk {
+            start: w[0].saturating_sub(overlap),
+            end: (w[1] + overlap).min(samples.len()),
+            own_start: w[0],
+            own_end: w[1],
+        })
+        .collect()
+}
+
+/// Combine the transcripts of all chunks, in order, into one.
+///
+/// Timestamps are shifted to be absolute, and only segments centred in the part of the audio
+/// a chunk owns are kept, so segments transcribed in the overlap of two chunks appear once.
+/// Where chunks overlap, a segment repeating the text of the segment right before it
+/// at a chunk boundary, at an intersecting time, is dropped too,
+/// in case both chunks placed it on their own side of the cut.
+/// Without overlap, both chunks heard different audio, so a repeated utterance is kept.
+fn stitch(chunks: &[Chunk], transcripts: Vec<Transcript>) -> Transcript {
+    let to_cs = |samples: usize| (samples * 100 / WHISPER_SAMPLE_RATE as usize) as i64;
+
+    let mut lang_id = -1;
+    let mut language = None;
+    let mut segments: Vec<Segment> = Vec::new();
+    for (i, (chunk, transcript)) in chunks.iter().zip(transcripts).enumerate() {
+        if lang_id == -1 {
+            lang_id = transcript.lang_id;
+            language = transcript.language;
+        }
+
+        let first_of_chunk = segments.len();
+        let overlaps_previous = i > 0 && chunk.start < chunks[i - 1].own_end;
+        for mut segment in transcript.segments {
+            segment.shift(to_cs(chunk.start));
+            let middle = (segment.t0 + segment.t1) / 2;
+            // segments running past either end of the whole input still belong to the outer chunks
+            let is_first = i == 0;
+            let is_last = i + 1 == chunks.len();
+            if (!is_first && middle < to_cs(chunk.own_start))
+                || (!is_last && middle >= to_cs(chunk.own_end))
+            {
+                continue;
+            }
+            let repeated = overlaps_previous
+                && segments.len() == first_of_chunk
+                && segments.last().is_some_and(|last| {
+                    last.t0 < segment.t1
+                        && segment.t0 < last.t1
+                        && last.text.trim() == segment.text.trim()
+                });
+            if !repeated {
+                segments.push(segment);
+            }
+        }
+    }
+
+    Transcript {
+        lang_id,
+        language,
+        segments,
+    }
+}
+
+/// Transcribes long recordings by splitting them into chunks at silence
+/// and running several chunks at once, each on its own [crate::WhisperState].
+///
+/// The results are stitched back together in order,
+/// with timestamps relative to the start of the whole recording.
+/// Chunks are transcribed independently, so text at the start of a chunk
+/// does not get the previous chunk as context like it would in a single call to
+/// [crate::WhisperState::full].
+///
+/// # Examples
+/// ```no_run
+/// # use whisper_rs::{ChunkedTranscriber, ChunkingConfig, FullParams, SamplingStrategy, WhisperContext};
+/// # fn run(ctx: &WhisperContext, samples: &[f32]) -> Result<(), whisper_rs::WhisperError> {
+/// let transcriber = ChunkedTranscriber::new(ctx, ChunkingConfig::default());
+/// let params = FullParams::new(SamplingStrategy::default());
+/// let transcript = transcriber.transcribe(&params, samples)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct ChunkedTranscriber<'c> {
+    ctx: &'c WhisperContext,
+    config: ChunkingConfig,
+}
+
+impl<'c> ChunkedTranscriber<'c> {
+    pub fn new(ctx: &'c WhisperContext, config: ChunkingConfig) -> Self {
+        Self { ctx, config }
+    }
+
+    pub fn config(&self) -> &ChunkingConfig {
+        &self.config
+    }
+
+    /// Transcribe PCM audio (32 bit floating point, 16 kHz, mono).
+    ///
+    /// `params` are used for every chunk.
+    /// Callbacks set on them are shared by all workers and may be called from several threads,
+    /// with timestamps relative to the chunk being transcribed.
+    ///
+    /// # Errors
+    /// Returns the error of the first chunk that failed, in order.
+    /// Once a chunk fails, no further chunks are started.
+    pub fn transcribe(
+        &self,
+        params: &FullParams,
+        samples: &[f32],
+    ) -> Result<Transcript, WhisperError> {
+        if samples.is_empty() {
+            return Err(WhisperError::NoSamples);
+        }
+
+        let chunks = plan_chunks(samples, &self.config);
+        transcribe_chunks(self.ctx, samples, &chunks, self.config.n_workers, |_| {
+            params.clone()
+        })
+    }
+}
+
+/// Transcribe `samples` split into `n_processors` equal parts, like whisper.cpp's
+/// `whisper_full_parallel`. Only the first part runs with the segment and progress callbacks.
+pub(crate) fn full_parallel(
+    ctx: &WhisperContext,
+    params: &FullParams,
+    samples: &[f32],
+    n_processors: c_int,
+) -> Result<Transcript, WhisperError> {
+    if n_processors < 1 {
+        return Err(WhisperError::InvalidThreadCount);
+    }
+    if samples.is_empty() {
+        return Err(WhisperError::NoSamples);
+    }
+
+    let chunks = split_evenly(samples.len(), n_processors as usize);
+    let n_parts = chunks.len();
+    let mut rest = params.clone();
+    rest.clear_segment_and_progress_callbacks();
+    transcribe_chunks(ctx, samples, &chunks, n_parts, |i| {
+        if i == 0 {
+            params.clone()
+        } else {
+            rest.clone()
+        }
+    })
+}
+
+/// Split `len` samples into `n_parts` equal chunks without overlap,
+/// or fewer if that would make them shorter than a second.
+fn split_evenly(len: usize, n_parts: usize) -> Vec<Chunk> {
+    let n_parts = n_parts.min(len / WHISPER_SAMPLE_RATE as usize).max(1);
+    let part_len = len / n_parts;
+    (0..n_parts)
+        .map(|i| {
+            let start = i * part_len;
+            // the last part takes the remainder
+            let end = if i + 1 == n_parts {
+                len
+            } else {
+                start + part_len
+            };
+            Chunk {
+                start,
+                end,
+                own_start: start,
+                own_end: end,
+            }
+        })
+        .collect()
+}
+
+/// Transcribe all `chunks` of `samples` on up to `n_workers` states at once,
+/// using `params_for(i)` as the parameters of the `i`th chunk, and stitch the results.
+fn transcribe_chunks<'a, 'b>(
+    ctx: &WhisperContext,
+    samples: &[f32],
+    chunks: &[Chunk],
+    n_workers: usize,
+    params_for: impl Fn(usize) -> FullParams<'a, 'b> + Sync,
+) -> Result<Transcript, WhisperError> {
+    let results = Mutex::new(Vec::with_capacity(chunks.len()));
+    let next = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+
+    let worker = || -> Result<(), WhisperError> {
+        let mut state = ctx.create_state()?;
+        loop {
+            let i = next.fetch_add(1, Ordering::Relaxed);
+            if i >= chunks.len() || failed.load(Ordering::Relaxed) {
+                return Ok(());
+            }
+            let chunk = chunks[i];
+            let result = state
+                .full(params_for(i), &samples[chunk.start..chunk.end])
+                .and_then(|_| state.transcript());
+            failed.fetch_or(result.is_err(), Ordering::Relaxed);
+            results
+                .lock()
+                .expect("no worker panics while holding the lock")
+                .push((i, result));
+        }
+    };
+
+    let n_workers = n_workers.clamp(1, chunks.len());
+    let worker_errors = std::thread::scope(|s| {
+        let handles = (0..n_workers).map(|_| s.spawn(worker)).collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .map(|h| h.join().unwrap_or_else(|e| std::panic::resume_unwind(e)))
+            .collect::<Vec<_>>()
+    });
+
+    let mut results = results.into_inner().expect("all workers have finished");
+    results.sort_by_key(|&(i, _)| i);
+    let transcripts = results
+        .into_iter()
+        .map(|(_, result)| result)
+        .collect::<Result<Vec<_>, _>>()?;
+    if transcripts.len() < chunks.len() {
+        // no chunk failed, so chunks were only left over if no worker could create a state
+        return Err(worker_errors
+            .into_iter()
+            .find_map(Result::err)
+            .unwrap_or(WhisperError::FailedToCreateState));
+    }
+
+    Ok(stitch(chunks, transcripts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{segment, transcript};
+
+    const RATE: usize = WHISPER_SAMPLE_RATE as usize;
+
+    /// Loud noise-like signal with 500 ms pauses at the given times, in seconds.
+    fn speech_with_pauses(seconds: usize, pauses: &[usize]) -> Vec<f32> {
+        let mut samples = (0..seconds * RATE)
+            .map(|i| 0.5 * ((i as f32 * 0.05).sin() + (i as f32 * 0.0131).sin()))
+            .collect::<Vec<_>>();
+        for &pause in pauses {
+            samples[pause * RATE..pause * RATE + RATE / 2].fill(0.0);
+        }
+        samples
+    }
+
+    #[test]
+    fn test_cuts_fall_into_pauses() {
+        let samples = speech_with_pauses(150, &[20, 45, 80, 100, 130]);
+        let chunks = plan_chunks(&samples, &ChunkingConfig::default());
+        let cuts = chunks[1..]
+            .iter()
+            .map(|c| c.own_start as f32 / RATE as f32)
+            .collect::<Vec<_>>();
+        // pauses at 20 and 100 are too early to cut at
+        assert_eq!(cuts.len(), 3, "{:?}", cuts);
+        for (cut, pause) in cuts.iter().zip([45.0, 80.0, 130.0]) {
+            assert!(*cut > pause && *cut < pause + 0.5, "{:?}", cuts);
+        }
+    }
+
+    #[test]
+    fn test_chunks_cover_input() {
+        let config = ChunkingConfig {
+            overlap_ms: 2000,
+            ..Default::default()
+        };
+        let samples = speech_with_pauses(400, &[]);
+        let chunks = plan_chunks(&samples, &config);
+        assert_eq!(chunks[0].own_start, 0);
+        assert_eq!(chunks.last().unwrap().own_end, samples.len());
+        for pair in chunks.windows(2) {
+            assert_eq!(pair[0].own_end, pair[1].own_start);
+        }
+        for chunk in &chunks {
+            let len = chunk.own_end - chunk.own_start;
+            assert!(len <= 60 * RATE);
+            assert_eq!(chunk.start, chunk.own_start.saturating_sub(2 * RATE));
+            assert_eq!(chunk.end, (chunk.own_end + 2 * RATE).min(samples.len()));
+        }
+        for chunk in &chunks[..chunks.len() - 1] {
+            assert!(chunk.own_end - chunk.own_start >= 30 * RATE);
+        }
+    }
+
+    #[test]
+    fn test_short_input_is_one_chunk() {
+        let samples = speech_with_pauses(45, &[10]);
+        let chunks = plan_chunks(&samples, &ChunkingConfig::default());
+        assert_eq!(
+            chunks,
+            [Chunk {
+                start: 0,
+                end: samples.len(),
+                own_start: 0,
+                own_end: samples.len()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_split_evenly() {
+        let chunks = split_evenly(10 * RATE + 3, 4);
+        assert_eq!(chunks.len(), 4);
+        assert_eq!(chunks[0].start, 0);
+        assert_eq!(chunks[3].end, 10 * RATE + 3);
+        for pair in chunks.windows(2) {
+            assert_eq!(pair[0].end, pair[1].start);
+        }
+        assert!(chunks
+            .iter()
+            .all(|c| c.own_start == c.start && c.own_end == c.end));
+
+        // no part shorter than a second
+        assert_eq!(split_evenly(3 * RATE, 8).len(), 3);
+        assert_eq!(split_evenly(100, 8).len(), 1);
+    }
+
+    #[test]
+    fn test_stitch_offsets_and_dedupes() {
+        let chunks = [
+            Chunk {
+                start: 0,
+                end: 41 * RATE,
+                own_start: 0,
+                own_end: 40 * RATE,
+            },
+            Chunk {
+                start: 39 * RATE,
+                end: 81 * RATE,
+                own_start: 40 * RATE,
+                own_end: 80 * RATE,
+            },
+            Chunk {
+                start: 79 * RATE,
+                end: 90 * RATE,
+                own_start: 80 * RATE,
+                own_end: 90 * RATE,
+            },
+        ];
+        let transcripts = vec![
+            transcript(vec![
+                segment(0, 1000, " One."),
+                segment(3800, 4050, " Two."),
+                // centred after the cut, so belongs to the second chunk
+                segment(4000, 4100, " Three."),
+            ]),
+            transcript(vec![
+                // the same segment, seen from the second chunk
+                segment(0, 150, " Two."),
+                segment(100, 200, " Three."),
+                segment(500, 900, " Four."),
+                segment(4050, 4140, " Five."),
+            ]),
+            transcript(vec![
+                // both chunks placed this on their own side of the cut
+                segment(70, 170, " Five."),
+                segment(200, 300, " Six."),
+            ]),
+        ];
+        let stitched = stitch(&chunks, transcripts);
+        let texts = stitched
+            .segments
+            .iter()
+            .map(|s| (s.t0, s.text.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            texts,
+            [
+                (0, " One."),
+                (3800, " Two."),
+                (4000, " Three."),
+                (4400, " Four."),
+                (7950, " Five."),
+                (8100, " Six.")
+            ]
+        );
+        assert_eq!(stitched.language.as_deref(), Some("en"));
+    }
+
+    #[test]
+    fn test_stitch_keeps_repeats_without_overlap() {
+        let chunks = split_evenly(80 * RATE, 2);
+        let transcripts = vec![
+            transcript(vec![segment(3900, 4000, " Yes.")]),
+            transcript(vec![segment(0, 80, " Yes.")]),
+        ];
+        let stitched = stitch(&chunks, transcripts);
+        let texts = stitched
+            .segments
+            .iter()
+            .map(|s| (s.t0, s.text.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(texts, [(3900, " Yes."), (4000, " Yes.")]);
+
+        // with overlap, but said twice in a row
+        let chunks = [
+            Chunk {
+                start: 0,
+                end: 41 * RATE,
+                own_start: 0,
+                own_end: 40 * RATE,
+            },
+            Chunk {
+                start: 39 * RATE,
+                end: 80 * RATE,
+                own_start: 40 * RATE,
+                own_end: 80 * RATE,
+            },
+        ];
+        let transcripts = vec![
+            transcript(vec![segment(3800, 3900, " Yes.")]),
+            transcript(vec![segment(110, 200, " Yes.")]),
+        ];
+        let stitched = stitch(&chunks, transcripts);
+        assert_eq!(stitched.segments.len(), 2);
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "test-with-tiny-model")]
+mod test_with_tiny_model {
+    use super::*;
+    use crate::test_util::{load_sample, MODEL_PATH};
+    use crate::{SamplingStrategy, WhisperContextParameters};
+
+    // These tests expect that the tiny.en model has been downloaded
+    // using the script `sys/whisper.cpp/models/download-ggml-model.sh tiny.en`
+
+    const RATE: usize = WHISPER_SAMPLE_RATE as usize;
+
+    #[test]
+    fn test_full_parallel() {
+        use std::sync::Arc;
+
+        let ctx = WhisperContext::new_with_params(MODEL_PATH, WhisperContextParameters::default())
+            .expect("Download the ggml-tiny.en model using 'sys/whisper.cpp/models/download-ggml-model.sh tiny.en'");
+        let sample = load_sample();
+        let samples = sample.repeat(2);
+
+        let callbacks = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&callbacks);
+        let mut params = FullParams::new(SamplingStrategy::default());
+        params.set_segment_callback_safe(move |_| {
+            counter.fetch_add(1, Ordering::Relaxed);
+        });
+
+        assert!(matches!(
+            ctx.full_parallel(&params, &samples, 0),
+            Err(WhisperError::InvalidThreadCount)
+        ));
+        let transcript = ctx.full_parallel(&params, &samples, 2).unwrap();
+        let first_part = transcript
+            .segments
+            .iter()
+            .filter(|s| s.t0 < (sample.len() / 160) as i64)
+            .count();
+        assert!(first_part > 0 && first_part < transcript.segments.len());
+        // only the first part reports new segments
+        assert_eq!(callbacks.load(Ordering::Relaxed), first_part);
+    }
+
+    #[test]
+    fn test_chunked_transcription() {
+        let ctx = WhisperContext::new_with_params(MODEL_PATH, WhisperContextParameters::default())
+            .expect("Download the ggml-tiny.en model using 'sys/whisper.cpp/models/download-ggml-model.sh tiny.en'");
+        let sample = load_sample();
+        // the sample followed by a second of silence, four times over
+        let mut samples = Vec::new();
+        for _ in 0..4 {
+            samples.extend_from_slice(&sample);
+            samples.resize(samples.len() + RATE, 0.0);
+        }
+
+        let mut params = FullParams::new(SamplingStrategy::default());
+        params.set_n_threads(1);
+        let config = ChunkingConfig {
+            min_chunk_ms: 5000,
+            max_chunk_ms: (sample.len() * 1000 / RATE) as u32 + 1000,
+            overlap_ms: 0,
+            n_workers: 2,
+        };
+        let transcript = ChunkedTranscriber::new(&ctx, config)
+            .transcribe(&params, &samples)
+            .unwrap();
+
+        assert!(!transcript.text().trim().is_empty());
+        assert!(transcript.segments.windows(2).all(|w| w[0].t0 <= w[1].t0));
+        // the last chunk starts after three copies of the sample
+        let last = transcript.segments.last().unwrap();
+        assert!(last.t1 > (3 * (sample.len() + RATE) / 160) as i64);
+        assert!(last.t1 <= (samples.len() / 160) as i64 + 100);
+    }
+}
@@ -48,6 +48,35 @@ pub enum WhisperError {
     InputOutputLengthMismatch { input_len: usize, output_len: usize },
     /// Input slice was not an even number of samples.
     HalfSampleMissing(usize),
+    /// The run was cancelled through a [crate::CancellationHandle] or its deadline passed.
+    Aborted,
+    /// The number of channels was 0.
+    InvalidChannelCount(usize),
+    /// Input slice did not contain a whole number of frames.
+    IncompleteFrame { len: usize, channels: usize },
+    /// The selected channel does not exist.
+    InvalidChannel { channel: usize, channels: usize },
+    /// The number of channel weights did not match the number of channels.
+    ChannelWeightsMismatch { weights: usize, channels: usize },
+    /// Input bytes did not contain a whole number of samples.
+    IncompleteSample { len: usize, sample_size: usize },
+    /// The VAD model failed to process the audio.
+    FailedToDetectSpeech,
+    /// Reading the model from a [std::io::Read] failed.
+    /// The underlying error is logged, if a logging backend is enabled.
+    FailedToReadModel,
+    /// Reading audio failed with an error of the given kind.
+    /// The underlying error is logged, if a logging backend is enabled.
+    FailedToReadAudio(std::io::ErrorKind),
+    /// The audio format or codec is not supported.
+    UnsupportedAudioFormat,
+    /// The audio is malformed and could not be decoded.
+    /// The underlying error is logged, if a logging backend is enabled.
+    FailedToDecodeAudio,
+    /// The input contains no decodable audio track.
+    NoAudioTrack,
+    /// The sample rate of the audio changed partway through.
+    SampleRateChanged { from: u32, to: u32 },
 }
 
 impl From<Utf8Error> for WhisperError {
@@ -134,6 +163,39 @@ impl std::fmt::Display for WhisperError {
                     size + 1
                 )
             }
+            Aborted => write!(f, "The run was cancelled."),
+            InvalidChannelCount(channels) => write!(f, "Invalid channel count: {}", channels),
+            IncompleteFrame { len, channels } => write!(
+                f,
+                "Input slice of {} samples does not contain a whole number of {}-channel frames",
+                len, channels
+            ),
+            InvalidChannel { channel, channels } => write!(
+                f,
+                "Channel {} does not exist in {}-channel audio",
+                channel, channels
+            ),
+            ChannelWeightsMismatch { weights, channels } => write!(
+                f,
+                "Got {} channel weights for {}-channel audio",
+                weights, channels
+            ),
+            IncompleteSample { len, sample_size } => write!(
+                f,
+                "Input of {} bytes does not contain a whole number of {}-byte samples",
+                len, sample_size
+            ),
+            FailedToDetectSpeech => write!(f, "Failed to detect speech."),
+            FailedToReadModel => write!(f, "Failed to read the model."),
+            FailedToReadAudio(kind) => write!(f, "Failed to read audio: {}", kind),
+            UnsupportedAudioFormat => write!(f, "Unsupported audio format."),
+            FailedToDecodeAudio => write!(f, "Failed to decode audio."),
+            NoAudioTrack => write!(f, "No audio track found."),
+            SampleRateChanged { from, to } => write!(
+                f,
+                "Sample rate changed from {} Hz to {} Hz partway through the audio",
+                from, to
+            ),
         }
     }
 }
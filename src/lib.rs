@@ -1,25 +1,57 @@
 #![allow(clippy::uninlined_format_args)]
 #![cfg_attr(test, feature(test))]
 
+#[cfg(feature = "audio-decode")]
+pub mod audio;
+#[cfg(feature = "models")]
+pub mod models;
+pub mod output;
+pub mod vad;
 #[cfg(feature = "vulkan")]
 pub mod vulkan;
 
+mod chunked;
 mod common_logging;
 mod error;
 mod ggml_logging_hook;
+mod model_info;
+mod multichannel;
+mod resampler;
 mod standalone;
+mod streaming;
+#[cfg(test)]
+mod test_util;
 mod utilities;
+#[cfg(feature = "async")]
+mod whisper_async;
+mod whisper_callback;
+mod whisper_cancellation;
 mod whisper_ctx;
 mod whisper_ctx_wrapper;
 mod whisper_grammar;
 mod whisper_logging_hook;
+mod whisper_logits_filter;
+mod whisper_model_loader;
 mod whisper_params;
+mod whisper_segment;
 mod whisper_state;
+mod whisper_timings;
+mod whisper_transcript;
+#[cfg(feature = "whisper-vad")]
+mod whisper_vad;
 
+pub use chunked::{ChunkedTranscriber, ChunkingConfig};
 pub use common_logging::GGMLLogLevel;
 pub use error::WhisperError;
+pub use model_info::{MelFilters, ModelInfo, ModelInfoError};
+pub use multichannel::{transcribe_channels, Conversation, Turn};
+pub use resampler::{resample_to_whisper, Resampler};
 pub use standalone::*;
+pub use streaming::{StreamingConfig, StreamingEvent, StreamingTranscriber};
 pub use utilities::*;
+#[cfg(feature = "async")]
+pub use whisper_async::{AsyncWhisperState, SegmentStream};
+pub use whisper_cancellation::CancellationHandle;
 pub use whisper_ctx::DtwMode;
 pub use whisper_ctx::DtwModelPreset;
 pub use whisper_ctx::DtwParameters;
@@ -27,10 +59,19 @@ pub use whisper_ctx::WhisperContextParameters;
 use whisper_ctx::WhisperInnerContext;
 pub use whisper_ctx_wrapper::WhisperContext;
 pub use whisper_grammar::{WhisperGrammarElement, WhisperGrammarElementType};
+pub use whisper_logits_filter::LogitsFilterContext;
 pub use whisper_params::{FullParams, SamplingStrategy, SegmentCallbackData};
 #[cfg(feature = "raw-api")]
 pub use whisper_rs_sys;
+pub use whisper_rs_sys::WHISPER_SAMPLE_RATE;
+pub use whisper_segment::{SegmentIter, SegmentRef, TokenIter, TokenRef};
 pub use whisper_state::WhisperState;
+pub use whisper_timings::RunTimings;
+pub use whisper_transcript::{Segment, Token, Transcript};
+#[cfg(feature = "whisper-vad")]
+pub use whisper_vad::{
+    WhisperVadContext, WhisperVadContextParams, WhisperVadParams, WhisperVadSegment,
+};
 
 pub type WhisperSysContext = whisper_rs_sys::whisper_context;
 pub type WhisperSysState = whisper_rs_sys::whisper_state;
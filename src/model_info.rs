@@ -0,0 +1,387 @@
+//! Reading the header of a GGML Whisper model without loading it into whisper.cpp.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+
+/// `ggml` in ASCII, as the first four bytes of every model file.
+const GGML_MAGIC: u32 = 0x67676d6c;
+/// `ftype` in the file is the tensor type plus this times the quantization version.
+const GGML_QNT_VERSION_FACTOR: i32 = 1000;
+/// Multilingual models have one more token than the English-only ones.
+const MULTILINGUAL_N_VOCAB: i32 = 51865;
+
+// Upper bounds that no real model comes close to,
+// so a corrupt header fails instead of allocating gigabytes.
+const MAX_MEL_FILTER_LEN: usize = 1 << 20;
+const MAX_TOKEN_LEN: usize = 1 << 16;
+const MAX_N_VOCAB: usize = 1 << 20;
+
+/// Mel filterbank stored in the model, used to compute the spectrogram.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MelFilters {
+    /// Number of mel bands.
+    pub n_mel: i32,
+    /// Number of FFT bins per band.
+    pub n_fft: i32,
+    /// The filters, `n_fft` weights for each of the `n_mel` bands.
+    pub data: Vec<f32>,
+}
+
+/// Header of a GGML Whisper model: the hyperparameters, mel filters and vocabulary.
+///
+/// The fields match the `model_*` getters of [crate::WhisperContext],
+/// but reading them only takes the first few hundred kilobytes of the file.
+///
+/// # Examples
+/// ```no_run
+/// # use whisper_rs::ModelInfo;
+/// # fn main() -> Result<(), whisper_rs::ModelInfoError> {
+/// let info = ModelInfo::from_file("ggml-large-v3.bin")?;
+/// assert_eq!(info.model_type(), Some("large"));
+/// assert_eq!(info.n_mels, 128);
+/// assert!(info.is_multilingual());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelInfo {
+    /// Size of the vocabulary, including special tokens.
+    pub n_vocab: i32,
+    /// Number of positions in the audio encoder.
+    pub n_audio_ctx: i32,
+    /// Width of the audio encoder.
+    pub n_audio_state: i32,
+    /// Number of attention heads of the audio encoder.
+    pub n_audio_head: i32,
+    /// Number of layers of the audio encoder.
+    pub n_audio_layer: i32,
+    /// Number of positions in the text decoder.
+    pub n_text_ctx: i32,
+    /// Width of the text decoder.
+    pub n_text_state: i32,
+    /// Number of attention heads of the text decoder.
+    pub n_text_head: i32,
+    /// Number of layers of the text decoder.
+    pub n_text_layer: i32,
+    /// Number of mel bands the model expects.
+    pub n_mels: i32,
+    /// Type of the weights, as a `ggml_ftype`. See [ModelInfo::ftype_name].
+    pub ftype: i32,
+    /// Quantization version. 0 for models that are not quantized.
+    pub qntvr: i32,
+    /// The mel filterbank.
+    pub mel_filters: MelFilters,
+    /// The regular tokens, as raw bytes by token ID.
+    /// They are not always valid UTF-8 on their own.
+    ///
+    /// This does not include the special tokens whisper.cpp adds up to [ModelInfo::n_vocab].
+    pub vocab: Vec<Vec<u8>>,
+}
+
+/// Errors that can occur while reading a model header.
+#[derive(Debug)]
+pub enum ModelInfoError {
+    /// Reading the input failed, or it ended before the end of the header.
+    Io(io::Error),
+    /// The input does not start with the GGML magic number.
+    InvalidMagic(u32),
+    /// A value in the header is out of range.
+    Invalid(&'static str),
+}
+
+impl From<io::Error> for ModelInfoError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl fmt::Display for ModelInfoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModelInfoError::Io(e) => write!(f, "Failed to read model: {}", e),
+            ModelInfoError::InvalidMagic(magic) => {
+                write!(f, "Not a GGML model (magic {:#010x}).", magic)
+            }
+            ModelInfoError::Invalid(what) => write!(f, "Invalid model header: {}.", what),
+        }
+    }
+}
+
+impl std::error::Error for ModelInfoError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ModelInfoError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl ModelInfo {
+    /// Read the header of the model file at `path`.
+    pub fn from_file(path: &str) -> Result<Self, ModelInfoError> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+
+    /// Read a model header from `reader`.
+    ///
+    /// Reading stops right after the vocabulary, where the tensors begin.
+    pub fn read<R: Read>(mut reader: R) -> Result<Self, ModelInfoError> {
+        let r = &mut reader;
+        let magic = read_u32(r)?;
+        if magic != GGML_MAGIC {
+            return Err(ModelInfoError::InvalidMagic(magic));
+        }
+
+        let n_vocab = read_i32(r)?;
+        let n_audio_ctx = read_i32(r)?;
+        let n_audio_state = read_i32(r)?;
+        let n_audio_head = read_i32(r)?;
+        let n_audio_layer = read_i32(r)?;
+        let n_text_ctx = read_i32(r)?;
+        let n_text_state = read_i32(r)?;
+        let n_text_head = read_i32(r)?;
+        let n_text_layer = read_i32(r)?;
+        let n_mels = read_i32(r)?;
+        let ftype = read_i32(r)?;
+
+        let n_mel = read_i32(r)?;
+        let n_fft = read_i32(r)?;
+        let len = checked_len(n_mel, n_fft, "mel filter size")?;
+        let mut bytes = vec![0; len * 4];
+        r.read_exact(&mut bytes)?;
+        let data = bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+
+        let n_tokens = read_i32(r)?;
+        let n_tokens = usize::try_from(n_tokens)
+            .ok()
+            .filter(|&n| n <= MAX_N_VOCAB)
+            .ok_or(ModelInfoError::Invalid("vocabulary size"))?;
+        let mut vocab = Vec::with_capacity(n_tokens);
+        for _ in 0..n_tokens {
+            let len = read_u32(r)? as usize;
+            if len > MAX_TOKEN_LEN {
+                return Err(ModelInfoError::Invalid("token length"));
+            }
+            let mut token = vec![0; len];
+            r.read_exact(&mut token)?;
+            vocab.push(token);
+        }
+
+        Ok(Self {
+            n_vocab,
+            n_audio_ctx,
+            n_audio_state,
+            n_audio_head,
+            n_audio_layer,
+            n_text_ctx,
+            n_text_state,
+            n_text_head,
+            n_text_layer,
+            n_mels,
+            ftype: ftype % GGML_QNT_VERSION_FACTOR,
+            qntvr: ftype / GGML_QNT_VERSION_FACTOR,
+            mel_filters: MelFilters { n_mel, n_fft, data },
+            vocab,
+        })
+    }
+
+    /// Does this model support multiple languages?
+    pub fn is_multilingual(&self) -> bool {
+        self.n_vocab >= MULTILINGUAL_N_VOCAB
+    }
+
+    /// Size of the model, as named by whisper.cpp: `tiny`, `base`, `small`, `medium` or `large`.
+    ///
+    /// Returns None for an unknown number of encoder layers.
+    pub fn model_type(&self) -> Option<&'static str> {
+        match self.n_audio_layer {
+            4 => Some("tiny"),
+            6 => Some("base"),
+            12 => Some("small"),
+            24 => Some("medium"),
+            32 => Some("large"),
+            _ => None,
+        }
+    }
+
+    /// Name of the weight type, such as `f16` or `q5_0`.
+    ///
+    /// Returns None for types whisper.cpp cannot quantize models to.
+    pub fn ftype_name(&self) -> Option<&'static str> {
+        match self.ftype {
+            0 => Some("f32"),
+            1 => Some("f16"),
+            2 => Some("q4_0"),
+            3 => Some("q4_1"),
+            4 => Some("q4_1_some_f16"),
+            7 => Some("q8_0"),
+            8 => Some("q5_0"),
+            9 => Some("q5_1"),
+            10 => Some("q2_k"),
+            11 => Some("q3_k"),
+            12 => Some("q4_k"),
+            13 => Some("q5_k"),
+            14 => Some("q6_k"),
+            _ => None,
+        }
+    }
+}
+
+fn read_u32<R: Read>(r: &mut R) -> io::Result<u32> {
+    let mut buf = [0; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_i32<R: Read>(r: &mut R) -> io::Result<i32> {
+    let mut buf = [0; 4];
+    r.read_exact(&mut buf)?;
+    Ok(i32::from_le_bytes(buf))
+}
+
+fn checked_len(a: i32, b: i32, what: &'static str) -> Result<usize, ModelInfoError> {
+    let a = usize::try_from(a).map_err(|_| ModelInfoError::Invalid(what))?;
+    let b = usize::try_from(b).map_err(|_| ModelInfoError::Invalid(what))?;
+    a.checked_mul(b)
+        .filter(|&len| len <= MAX_MEL_FILTER_LEN)
+        .ok_or(ModelInfoError::Invalid(what))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Header of a tiny multilingual model, with a shortened mel filterbank and vocabulary.
+    fn header(ftype: i32, vocab: &[&[u8]]) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend(GGML_MAGIC.to_le_bytes());
+        for value in [51865, 1500, 384, 6, 4, 448, 384, 6, 4, 80, ftype] {
+            out.extend(i32::to_le_bytes(value));
+        }
+        out.extend(2i32.to_le_bytes());
+        out.extend(3i32.to_le_bytes());
+        for value in [0.0f32, 0.5, 1.0, 1.0, 0.5, 0.0] {
+            out.extend(value.to_le_bytes());
+        }
+        out.extend((vocab.len() as i32).to_le_bytes());
+        for token in vocab {
+            out.extend((token.len() as u32).to_le_bytes());
+            out.extend(*token);
+        }
+        out
+    }
+
+    #[test]
+    fn test_read_header() {
+        let mut bytes = header(2008, &[b"!", b" the", &[0xe2, 0x80]]);
+        // the tensors that follow are not read
+        bytes.extend([0xff; 16]);
+        let mut reader = &bytes[..];
+        let info = ModelInfo::read(&mut reader).unwrap();
+        assert_eq!(reader.len(), 16);
+
+        assert_eq!(info.n_vocab, 51865);
+        assert_eq!(info.n_audio_ctx, 1500);
+        assert_eq!(info.n_text_layer, 4);
+        assert_eq!(info.n_mels, 80);
+        assert_eq!(info.ftype, 8);
+        assert_eq!(info.qntvr, 2);
+        assert_eq!(info.ftype_name(), Some("q5_0"));
+        assert_eq!(info.model_type(), Some("tiny"));
+        assert!(info.is_multilingual());
+        assert_eq!(
+            info.mel_filters,
+            MelFilters {
+                n_mel: 2,
+                n_fft: 3,
+                data: vec![0.0, 0.5, 1.0, 1.0, 0.5, 0.0]
+            }
+        );
+        assert_eq!(info.vocab, [&b"!"[..], b" the", &[0xe2, 0x80]]);
+    }
+
+    #[test]
+    fn test_invalid_magic() {
+        let mut bytes = header(1, &[]);
+        bytes[..4].copy_from_slice(b"GGUF");
+        assert!(matches!(
+            ModelInfo::read(&bytes[..]),
+            Err(ModelInfoError::InvalidMagic(0x46554747))
+        ));
+    }
+
+    #[test]
+    fn test_truncated() {
+        let bytes = header(1, &[b"hello"]);
+        for len in [0, 10, bytes.len() - 1] {
+            assert!(matches!(
+                ModelInfo::read(&bytes[..len]),
+                Err(ModelInfoError::Io(e)) if e.kind() == io::ErrorKind::UnexpectedEof
+            ));
+        }
+    }
+
+    #[test]
+    fn test_corrupt_sizes() {
+        let mut bytes = header(1, &[]);
+        // n_mel
+        bytes[48..52].copy_from_slice(&(-1i32).to_le_bytes());
+        assert!(matches!(
+            ModelInfo::read(&bytes[..]),
+            Err(ModelInfoError::Invalid("mel filter size"))
+        ));
+
+        let mut bytes = header(1, &[b"a"]);
+        let token_len = bytes.len() - 5;
+        bytes[token_len..token_len + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            ModelInfo::read(&bytes[..]),
+            Err(ModelInfoError::Invalid("token length"))
+        ));
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "test-with-tiny-model")]
+mod test_with_tiny_model {
+    use super::*;
+    use crate::test_util::MODEL_PATH;
+    use crate::{WhisperContext, WhisperContextParameters};
+
+    // These tests expect that the tiny.en model has been downloaded
+    // using the script `sys/whisper.cpp/models/download-ggml-model.sh tiny.en`
+
+    #[test]
+    fn test_matches_context() {
+        let info = ModelInfo::from_file(MODEL_PATH).expect("Download the ggml-tiny.en model using 'sys/whisper.cpp/models/download-ggml-model.sh tiny.en'");
+        let ctx = WhisperContext::new_with_params(MODEL_PATH, WhisperContextParameters::default())
+            .unwrap();
+
+        assert_eq!(info.n_vocab, ctx.model_n_vocab());
+        assert_eq!(info.n_audio_ctx, ctx.model_n_audio_ctx());
+        assert_eq!(info.n_audio_state, ctx.model_n_audio_state());
+        assert_eq!(info.n_audio_head, ctx.model_n_audio_head());
+        assert_eq!(info.n_audio_layer, ctx.model_n_audio_layer());
+        assert_eq!(info.n_text_ctx, ctx.model_n_text_ctx());
+        assert_eq!(info.n_text_state, ctx.model_n_text_state());
+        assert_eq!(info.n_text_head, ctx.model_n_text_head());
+        assert_eq!(info.n_text_layer, ctx.model_n_text_layer());
+        assert_eq!(info.n_mels, ctx.model_n_mels());
+        assert_eq!(info.ftype, ctx.model_ftype());
+        assert_eq!(info.is_multilingual(), ctx.is_multilingual());
+        assert_eq!(
+            info.model_type(),
+            Some(ctx.model_type_readable().unwrap().as_str())
+        );
+        assert_eq!(info.mel_filters.n_mel, info.n_mels);
+
+        // the special tokens follow the regular ones
+        let eot = ctx.token_eot() as usize;
+        assert_eq!(info.vocab.len(), eot);
+        assert_eq!(info.vocab[100], ctx.token_to_cstr(100).unwrap().to_bytes());
+    }
+}
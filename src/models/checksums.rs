@@ -0,0 +1,5 @@
+// Generated by scripts/update-model-checksums.py from the Git LFS metadata of
+// https://huggingface.co/ggerganov/whisper.cpp, do not edit by hand.
+
+/// Size in bytes and SHA-256 of each published model file, by model name.
+pub(super) const CHECKSUMS: &[(&str, u64, &str)] = &[];
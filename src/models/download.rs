@@ -0,0 +1,656 @@
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use super::{Model, ModelCache, ModelError};
+
+/// Where whisper.cpp's download script fetches the models from.
+pub const DEFAULT_BASE_URL: &str = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main";
+
+/// Progress of a download, passed to the callback set with [Downloader::with_progress].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DownloadProgress {
+    /// Bytes of the model file downloaded so far, including those of an earlier, interrupted download.
+    pub downloaded: u64,
+    /// Size of the model file, if the server reported it.
+    pub total: Option<u64>,
+}
+
+/// Downloads models into a [ModelCache].
+///
+/// Requires the `download` feature.
+///
+/// The model is downloaded to a `.part` file next to its final location.
+/// If that file is left behind by an interrupted download, the download resumes where it stopped,
+/// provided the server supports range requests and sent an `ETag` for the file.
+/// The `ETag` is sent back in `If-Range`, so a file that changed on the server since
+/// is downloaded again from the start instead of being spliced onto the old data.
+/// Once complete, the file is checked with [Model::verify], including its SHA-256,
+/// and renamed into place, so the cache never contains a partial or unverified model.
+///
+/// # Examples
+/// ```no_run
+/// # use whisper_rs::models::{Downloader, Model, ModelCache};
+/// # fn main() -> Result<(), whisper_rs::models::ModelError> {
+/// let cache = ModelCache::from_env()?;
+/// let model = Model::by_name("base.en").unwrap();
+/// let path = Downloader::new()
+///     .with_progress(|p| eprint!("\r{} / {:?} bytes", p.downloaded, p.total))
+///     .download(&cache, &model)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Downloader {
+    base_url: String,
+    agent: ureq::Agent,
+    progress: Option<Box<dyn FnMut(DownloadProgress) + Send>>,
+}
+
+impl Default for Downloader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Downloader {
+    /// Download from [DEFAULT_BASE_URL], without progress reporting.
+    pub fn new() -> Self {
+        Self {
+            base_url: DEFAULT_BASE_URL.to_string(),
+            agent: ureq::agent(),
+            progress: None,
+        }
+    }
+
+    /// Download from a mirror instead. The model files are expected directly under `base_url`,
+    /// such as `{base_url}/ggml-base.en.bin`.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Call `progress` after every chunk written to disk.
+    pub fn with_progress<F>(mut self, progress: F) -> Self
+    where
+        F: FnMut(DownloadProgress) + Send + 'static,
+    {
+        self.progress = Some(Box::new(progress));
+        self
+    }
+
+    /// The URL `model` is downloaded from.
+    pub fn url(&self, model: &Model) -> String {
+        format!(
+            "{}/{}",
+            self.base_url.trim_end_matches('/'),
+            model.file_name()
+        )
+    }
+
+    /// Download `model` into `cache`, unless it is already there and passes [ModelCache::verify].
+    ///
+    /// # Returns
+    /// The path to the verified model file.
+    ///
+    /// # Errors
+    /// * [ModelError::Download] if the request fails,
+    ///   or if `model` has no SHA-256 to check the download against
+    /// * [ModelError::Io] if writing to the cache fails
+    /// * any error of [Model::verify] if the downloaded file is not the model.
+    ///   The downloaded file is deleted, so the next attempt starts over.
+    pub fn download(&mut self, cache: &ModelCache, model: &Model) -> Result<PathBuf, ModelError> {
+        if model.sha256().is_none() {
+            return Err(ModelError::Download(
+                format!(
+                    "no SHA-256 known for {}, set one with Model::with_sha256",
+                    model
+                )
+                .into(),
+            ));
+        }
+        if let Ok(path) = cache.verify(model) {
+            return Ok(path);
+        }
+
+        fs::create_dir_all(cache.dir())?;
+        let path = cache.path(model);
+        let part = path.with_file_name(format!("{}.part", model.file_name()));
+        let url = self.url(model);
+        self.fetch(&url, &part)?;
+
+        let verified = model.verify(&part);
+        if verified.is_err() {
+            fs::remove_file(&part)?;
+        }
+        remove_if_exists(&etag_path(&part))?;
+        verified?;
+        fs::rename(&part, &path)?;
+        Ok(path)
+    }
+
+    /// Download `url` into `part`, resuming from the data already in it
+    /// if the `ETag` it was downloaded with is known.
+    fn fetch(&mut self, url: &str, part: &Path) -> Result<(), ModelError> {
+        let etag_path = etag_path(part);
+        let offset = fs::metadata(part).map_or(0, |m| m.len());
+        let etag = fs::read_to_string(&etag_path).ok().filter(|_| offset > 0);
+        let mut request = self.agent.get(url);
+        if let Some(etag) = &etag {
+            request = request
+                .set("Range", &format!("bytes={}-", offset))
+                .set("If-Range", etag);
+        }
+        let response = match request.call() {
+            Ok(response) => response,
+            Err(ureq::Error::Status(416, response)) if etag.is_some() => {
+                let total = response
+                    .header("Content-Range")
+                    .and_then(parse_unsatisfied_range);
+                // the earlier download had already finished, but was not renamed into place
+                if total == Some(offset) {
+                    return Ok(());
+                }
+                // not a prefix of the file, start over
+                fs::remove_file(part)?;
+                fs::remove_file(&etag_path)?;
+                return self.fetch(url, part);
+            }
+            Err(e) => return Err(ModelError::Download(Box::new(e))),
+        };
+
+        let resumed = response.status() == 206;
+        let (mut downloaded, total) = if resumed {
+            let (start, total) = response
+                .header("Content-Range")
+                .and_then(parse_content_range)
+                .ok_or_else(|| ModelError::Download("invalid Content-Range".into()))?;
+            if start != offset {
+                return Err(ModelError::Download(
+                    format!("asked to resume at byte {}, got byte {}", offset, start).into(),
+                ));
+            }
+            (offset, total)
+        } else {
+            // the server sends the whole file
+            let total = response
+                .header("Content-Length")
+                .and_then(|len| len.parse().ok());
+            (0, total)
+        };
+
+        let mut file = if resumed {
+            OpenOptions::new().append(true).open(part)?
+        } else {
+            let file = fs::File::create(part)?;
+            // weak validators are not allowed in If-Range
+            match response
+                .header("ETag")
+                .filter(|etag| !etag.starts_with("W/"))
+            {
+                Some(etag) => fs::write(&etag_path, etag)?,
+                None => remove_if_exists(&etag_path)?,
+            }
+            file
+        };
+        let mut body = response.into_reader();
+        let mut buf = vec![0; 1 << 16];
+        self.report(downloaded, total);
+        loop {
+            let n = match body.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(ModelError::Download(Box::new(e))),
+            };
+            file.write_all(&buf[..n])?;
+            downloaded += n as u64;
+            self.report(downloaded, total);
+        }
+        if total.is_some_and(|total| downloaded != total) {
+            return Err(ModelError::Download(
+                format!(
+                    "connection closed after {} of {} bytes",
+                    downloaded,
+                    total.unwrap_or_default()
+                )
+                .into(),
+            ));
+        }
+        file.sync_all()?;
+        Ok(())
+    }
+
+    fn report(&mut self, downloaded: u64, total: Option<u64>) {
+        if let Some(progress) = &mut self.progress {
+            progress(DownloadProgress { downloaded, total });
+        }
+    }
+}
+
+impl ModelCache {
+    /// Download `model` into this cache with a default [Downloader],
+    /// unless it is already there and passes [ModelCache::verify].
+    ///
+    /// Requires the `download` feature.
+    pub fn download(&self, model: &Model) -> Result<PathBuf, ModelError> {
+        Downloader::new().download(self, model)
+    }
+}
+
+/// Where the `ETag` of the download into `part` is kept.
+fn etag_path(part: &Path) -> PathBuf {
+    let mut name = part.as_os_str().to_owned();
+    name.push(".etag");
+    PathBuf::from(name)
+}
+
+fn remove_if_exists(path: &Path) -> std::io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Parse `bytes START-END/TOTAL` into the start and, unless it is `*`, the total.
+fn parse_content_range(value: &str) -> Option<(u64, Option<u64>)> {
+    let (range, total) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let (start, _end) = range.split_once('-')?;
+    let total = if total == "*" {
+        None
+    } else {
+        Some(total.parse().ok()?)
+    };
+    Some((start.parse().ok()?, total))
+}
+
+/// Parse the `bytes */TOTAL` of a 416 response into the total.
+fn parse_unsatisfied_range(value: &str) -> Option<u64> {
+    value.strip_prefix("bytes */")?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{fake_model, pin_to};
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    #[derive(Clone, Copy, PartialEq)]
+    pub(super) enum Mode {
+        Ranges,
+        NoRanges,
+        NotFound,
+    }
+
+    /// Path, `Range` and `If-Range` header of each request.
+    pub(super) type RequestLog = Arc<Mutex<Vec<(String, Option<String>, Option<String>)>>>;
+
+    const ETAG: &str = "\"v1\"";
+
+    /// A minimal HTTP server serving `body` with [ETAG] on any path, standing in for Hugging Face.
+    /// Records the path, `Range` and `If-Range` header of every request.
+    pub(super) fn serve(body: Vec<u8>, mode: Mode) -> (String, RequestLog) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/models", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&requests);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(&stream);
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let path = line.split(' ').nth(1).unwrap().to_string();
+                let mut range = None;
+                let mut if_range = None;
+                loop {
+                    line.clear();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':') {
+                        if name.eq_ignore_ascii_case("range") {
+                            range = Some(value.trim().to_string());
+                        } else if name.eq_ignore_ascii_case("if-range") {
+                            if_range = Some(value.trim().to_string());
+                        }
+                    }
+                }
+                log.lock()
+                    .unwrap()
+                    .push((path, range.clone(), if_range.clone()));
+
+                let start = range
+                    .filter(|_| mode == Mode::Ranges)
+                    // the file changed since the earlier download, send all of it
+                    .filter(|_| if_range.as_deref().is_none_or(|etag| etag == ETAG))
+                    .and_then(|r| r.strip_prefix("bytes=")?.strip_suffix('-')?.parse().ok());
+                let (status, headers, data) = match (mode, start) {
+                    (Mode::NotFound, _) => ("404 Not Found", String::new(), &[][..]),
+                    (_, Some(start)) if start >= body.len() => (
+                        "416 Range Not Satisfiable",
+                        format!("Content-Range: bytes */{}\r\n", body.len()),
+                        &[][..],
+                    ),
+                    (_, Some(start)) => (
+                        "206 Partial Content",
+                        format!(
+                            "Content-Range: bytes {}-{}/{}\r\n",
+                            start,
+                            body.len() - 1,
+                            body.len()
+                        ),
+                        &body[start..],
+                    ),
+                    (_, None) => ("200 OK", format!("ETag: {}\r\n", ETAG), &body[..]),
+                };
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    headers,
+                    data.len()
+                )
+                .unwrap();
+                stream.write_all(data).unwrap();
+            }
+        });
+        (url, requests)
+    }
+
+    pub(super) fn temp_cache(name: &str) -> ModelCache {
+        let dir = std::env::temp_dir().join(format!(
+            "whisper-rs-download-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        ModelCache::new(dir)
+    }
+
+    fn model() -> (Model, Vec<u8>) {
+        let model = Model::by_name("tiny.en").unwrap();
+        let mut body = fake_model(&model);
+        // tensors are not checked, but make the file span several reads
+        body.extend((0..200_000u32).map(|i| i as u8));
+        (pin_to(model, &body), body)
+    }
+
+    /// Leave `data` behind as an interrupted download of tiny.en, made with `etag` if any.
+    fn write_part(cache: &ModelCache, data: &[u8], etag: Option<&str>) -> PathBuf {
+        fs::create_dir_all(cache.dir()).unwrap();
+        let part = cache.dir().join("ggml-tiny.en.bin.part");
+        fs::write(&part, data).unwrap();
+        if let Some(etag) = etag {
+            fs::write(etag_path(&part), etag).unwrap();
+        }
+        part
+    }
+
+    #[test]
+    fn test_download() {
+        let (model, body) = model();
+        let (url, requests) = serve(body.clone(), Mode::Ranges);
+        let cache = temp_cache("fresh");
+        let progress = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&progress);
+        let mut downloader = Downloader::new()
+            .with_base_url(&url)
+            .with_progress(move |p| log.lock().unwrap().push(p));
+
+        let path = downloader.download(&cache, &model).unwrap();
+        assert_eq!(path, cache.path(&model));
+        assert_eq!(fs::read(&path).unwrap(), body);
+        // nothing is left behind
+        assert_eq!(fs::read_dir(cache.dir()).unwrap().count(), 1);
+        assert_eq!(
+            *requests.lock().unwrap(),
+            [("/models/ggml-tiny.en.bin".to_string(), None, None)]
+        );
+
+        let progress = progress.lock().unwrap();
+        assert!(progress.len() > 2);
+        assert!(progress
+            .windows(2)
+            .all(|w| w[0].downloaded <= w[1].downloaded));
+        assert_eq!(
+            progress.last(),
+            Some(&DownloadProgress {
+                downloaded: body.len() as u64,
+                total: Some(body.len() as u64)
+            })
+        );
+
+        // already in the cache
+        downloader.download(&cache, &model).unwrap();
+        assert_eq!(requests.lock().unwrap().len(), 1);
+        fs::remove_dir_all(cache.dir()).unwrap();
+    }
+
+    #[test]
+    fn test_resume() {
+        let (model, body) = model();
+        let (url, requests) = serve(body.clone(), Mode::Ranges);
+        let cache = temp_cache("resume");
+        let part = write_part(&cache, &body[..1000], Some(ETAG));
+
+        let mut first = None;
+        let path = Downloader::new()
+            .with_base_url(&url)
+            .with_progress(move |p| {
+                first.get_or_insert(p);
+                assert_eq!(first.unwrap().downloaded, 1000);
+            })
+            .download(&cache, &model)
+            .unwrap();
+        assert_eq!(fs::read(path).unwrap(), body);
+        assert_eq!(
+            requests.lock().unwrap()[0],
+            (
+                "/models/ggml-tiny.en.bin".to_string(),
+                Some("bytes=1000-".to_string()),
+                Some(ETAG.to_string())
+            )
+        );
+        assert!(!etag_path(&part).exists());
+
+        // a finished download that was not renamed yet
+        fs::rename(cache.path(&model), &part).unwrap();
+        fs::write(etag_path(&part), ETAG).unwrap();
+        let path = Downloader::new()
+            .with_base_url(&url)
+            .download(&cache, &model)
+            .unwrap();
+        assert_eq!(fs::read(path).unwrap(), body);
+        assert_eq!(requests.lock().unwrap().len(), 2);
+        fs::remove_dir_all(cache.dir()).unwrap();
+    }
+
+    #[test]
+    fn test_resume_changed_file() {
+        let (model, body) = model();
+        let (url, requests) = serve(body.clone(), Mode::Ranges);
+        let cache = temp_cache("changed");
+        write_part(&cache, &[0xff; 1000], Some("\"v0\""));
+
+        let path = Downloader::new()
+            .with_base_url(&url)
+            .download(&cache, &model)
+            .unwrap();
+        assert_eq!(fs::read(path).unwrap(), body);
+        assert_eq!(requests.lock().unwrap().len(), 1);
+        fs::remove_dir_all(cache.dir()).unwrap();
+    }
+
+    #[test]
+    fn test_no_resume_without_etag() {
+        let (model, body) = model();
+        let (url, requests) = serve(body.clone(), Mode::Ranges);
+        let cache = temp_cache("no-etag");
+        write_part(&cache, &body[..1000], None);
+
+        let path = Downloader::new()
+            .with_base_url(&url)
+            .download(&cache, &model)
+            .unwrap();
+        assert_eq!(fs::read(path).unwrap(), body);
+        assert_eq!(requests.lock().unwrap()[0].1, None);
+        fs::remove_dir_all(cache.dir()).unwrap();
+    }
+
+    #[test]
+    fn test_part_longer_than_file() {
+        let (model, body) = model();
+        let (url, requests) = serve(body.clone(), Mode::Ranges);
+        let cache = temp_cache("too-long");
+        let mut data = body.clone();
+        data.extend([0xff; 10]);
+        write_part(&cache, &data, Some(ETAG));
+
+        let path = Downloader::new()
+            .with_base_url(&url)
+            .download(&cache, &model)
+            .unwrap();
+        assert_eq!(fs::read(path).unwrap(), body);
+        let requests = requests.lock().unwrap();
+        // the 416 is answered by starting over
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].1, None);
+        fs::remove_dir_all(cache.dir()).unwrap();
+    }
+
+    #[test]
+    fn test_unpinned_model() {
+        let cache = temp_cache("unpinned");
+        let Some(model) = Model::all().iter().find(|m| m.sha256().is_none()) else {
+            // every model has a published SHA-256
+            return;
+        };
+        let result = Downloader::new()
+            .with_base_url("http://127.0.0.1:9")
+            .download(&cache, model);
+        assert!(matches!(result, Err(ModelError::Download(_))));
+        assert!(!cache.dir().exists());
+    }
+
+    #[test]
+    fn test_server_without_ranges() {
+        let (model, body) = model();
+        let (url, _) = serve(body.clone(), Mode::NoRanges);
+        let cache = temp_cache("no-ranges");
+        write_part(&cache, &[0xff; 1000], Some(ETAG));
+
+        let path = Downloader::new()
+            .with_base_url(&url)
+            .download(&cache, &model)
+            .unwrap();
+        assert_eq!(fs::read(path).unwrap(), body);
+        fs::remove_dir_all(cache.dir()).unwrap();
+    }
+
+    #[test]
+    fn test_checksum_mismatch() {
+        let (model, body) = model();
+        let (url, _) = serve(body, Mode::Ranges);
+        let cache = temp_cache("checksum");
+        let model = model.with_sha256(&"0".repeat(64)).unwrap();
+
+        let result = Downloader::new()
+            .with_base_url(&url)
+            .download(&cache, &model);
+        assert!(matches!(result, Err(ModelError::ChecksumMismatch { .. })));
+        // nothing is left behind
+        assert_eq!(fs::read_dir(cache.dir()).unwrap().count(), 0);
+        fs::remove_dir_all(cache.dir()).unwrap();
+    }
+
+    #[test]
+    fn test_not_found() {
+        let (model, body) = model();
+        let (url, _) = serve(body, Mode::NotFound);
+        let cache = temp_cache("not-found");
+        let result = Downloader::new()
+            .with_base_url(&url)
+            .download(&cache, &model);
+        let Err(ModelError::Download(e)) = result else {
+            panic!("expected a download error, got {:?}", result);
+        };
+        assert!(matches!(
+            e.downcast_ref::<ureq::Error>(),
+            Some(ureq::Error::Status(404, _))
+        ));
+        assert!(!cache.path(&model).exists());
+        let _ = fs::remove_dir_all(cache.dir());
+    }
+
+    #[test]
+    fn test_parse_content_range() {
+        assert_eq!(
+            parse_content_range("bytes 100-199/200"),
+            Some((100, Some(200)))
+        );
+        assert_eq!(parse_content_range("bytes 0-9/*"), Some((0, None)));
+        assert_eq!(parse_content_range("100-199/200"), None);
+        assert_eq!(parse_unsatisfied_range("bytes */200"), Some(200));
+        assert_eq!(parse_unsatisfied_range("bytes 0-9/200"), None);
+    }
+
+    #[test]
+    fn test_url() {
+        let model = Model::by_name("large-v3-turbo").unwrap();
+        assert_eq!(
+            Downloader::new().url(&model),
+            "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-large-v3-turbo.bin"
+        );
+        assert_eq!(
+            Downloader::new()
+                .with_base_url("http://mirror/")
+                .url(&model),
+            "http://mirror/ggml-large-v3-turbo.bin"
+        );
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "test-with-tiny-model")]
+mod test_with_tiny_model {
+    use super::tests::{serve, temp_cache, Mode};
+    use super::*;
+
+    // These tests expect that the tiny.en model has been downloaded
+    // using the script `sys/whisper.cpp/models/download-ggml-model.sh tiny.en`
+
+    #[test]
+    fn test_download_catalog_model() {
+        let model = Model::by_name("tiny.en").unwrap();
+        assert!(
+            model.sha256().is_some(),
+            "tiny.en has no published SHA-256, run scripts/update-model-checksums.py"
+        );
+        let body = fs::read("./sys/whisper.cpp/models/ggml-tiny.en.bin")
+            .expect("Download the ggml-tiny.en model using 'sys/whisper.cpp/models/download-ggml-model.sh tiny.en'");
+
+        let (url, _) = serve(body.clone(), Mode::Ranges);
+        let cache = temp_cache("catalog");
+        let path = Downloader::new()
+            .with_base_url(&url)
+            .download(&cache, &model)
+            .unwrap();
+        assert_eq!(fs::read(&path).unwrap(), body);
+        fs::remove_dir_all(cache.dir()).unwrap();
+
+        // same size and header, different weights
+        let mut corrupt = body;
+        *corrupt.last_mut().unwrap() ^= 1;
+        let (url, _) = serve(corrupt, Mode::Ranges);
+        let cache = temp_cache("catalog-corrupt");
+        let result = Downloader::new()
+            .with_base_url(&url)
+            .download(&cache, &model);
+        assert!(matches!(result, Err(ModelError::ChecksumMismatch { .. })));
+        fs::remove_dir_all(cache.dir()).unwrap();
+    }
+}
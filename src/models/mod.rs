@@ -0,0 +1,750 @@
+//! The standard ggml Whisper models, and a local directory to keep them in.
+//!
+//! Requires the `models` feature.
+//!
+//! [Model] describes one of the models published for whisper.cpp, such as `base.en` or
+//! `large-v3-turbo-q5_0`, and [ModelCache] finds and verifies them in a cache directory,
+//! so the model path no longer needs to be hardcoded:
+//!
+//! ```no_run
+//! # use whisper_rs::models::{Model, ModelCache};
+//! # use whisper_rs::WhisperContextParameters;
+//! # fn main() -> Result<(), whisper_rs::models::ModelError> {
+//! let model = Model::by_name("base.en").unwrap();
+//! let cache = ModelCache::from_env()?;
+//! let ctx = cache.load(&model, WhisperContextParameters::default())?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Every file is checked against the architecture and weight type its name implies,
+//! which catches mixed up models and broken headers, and against the size and SHA-256
+//! published for it on Hugging Face, which catches truncated, corrupt or tampered weights.
+//! The published values are generated into `src/models/checksums.rs` by
+//! `scripts/update-model-checksums.py`; a model missing from that table has only its header
+//! checked, which is logged as a warning, and cannot be downloaded.
+//! Use [Model::with_sha256] and [Model::with_size] to check against other values,
+//! e.g. for a model file you converted or quantized yourself.
+//!
+//! With the `download` feature, [Downloader] fetches missing models into the cache.
+
+mod checksums;
+#[cfg(feature = "download")]
+mod download;
+
+use std::env;
+use std::ffi::OsString;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+
+#[cfg(feature = "download")]
+pub use download::{DownloadProgress, Downloader, DEFAULT_BASE_URL};
+
+use crate::common_logging::generic_warn;
+use crate::{
+    DtwModelPreset, ModelInfo, ModelInfoError, WhisperContext, WhisperContextParameters,
+    WhisperError,
+};
+
+/// Environment variable overriding the cache directory, see [ModelCache::from_env].
+pub const MODEL_DIR_ENV: &str = "WHISPER_RS_MODEL_DIR";
+
+/// Architecture shared by all variants of a model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Arch {
+    n_audio_layer: i32,
+    n_text_layer: i32,
+    n_mels: i32,
+    multilingual: bool,
+    dtw_preset: DtwModelPreset,
+}
+
+const fn arch(layers: i32, multilingual: bool, dtw_preset: DtwModelPreset) -> Arch {
+    Arch {
+        n_audio_layer: layers,
+        n_text_layer: layers,
+        n_mels: 80,
+        multilingual,
+        dtw_preset,
+    }
+}
+
+const TINY_EN: Arch = arch(4, false, DtwModelPreset::TinyEn);
+const TINY: Arch = arch(4, true, DtwModelPreset::Tiny);
+const BASE_EN: Arch = arch(6, false, DtwModelPreset::BaseEn);
+const BASE: Arch = arch(6, true, DtwModelPreset::Base);
+const SMALL_EN: Arch = arch(12, false, DtwModelPreset::SmallEn);
+const SMALL: Arch = arch(12, true, DtwModelPreset::Small);
+const MEDIUM_EN: Arch = arch(24, false, DtwModelPreset::MediumEn);
+const MEDIUM: Arch = arch(24, true, DtwModelPreset::Medium);
+const LARGE_V1: Arch = arch(32, true, DtwModelPreset::LargeV1);
+const LARGE_V2: Arch = arch(32, true, DtwModelPreset::LargeV2);
+const LARGE_V3: Arch = Arch {
+    n_mels: 128,
+    ..arch(32, true, DtwModelPreset::LargeV3)
+};
+const LARGE_V3_TURBO: Arch = Arch {
+    n_text_layer: 4,
+    n_mels: 128,
+    ..arch(32, true, DtwModelPreset::LargeV3Turbo)
+};
+
+// `ggml_ftype` of the published weights
+const F16: i32 = 1;
+const Q8_0: i32 = 7;
+const Q5_0: i32 = 8;
+const Q5_1: i32 = 9;
+
+const fn model(name: &'static str, arch: Arch, ftype: i32) -> Model {
+    let (size, sha256) = match published(name) {
+        Some((size, sha256)) => (Some(size), Some(parse_sha256_const(sha256))),
+        None => (None, None),
+    };
+    Model {
+        name,
+        arch,
+        ftype,
+        sha256,
+        size,
+    }
+}
+
+/// Size and SHA-256 of the published file of model `name`, if known.
+const fn published(name: &str) -> Option<(u64, &'static str)> {
+    let mut i = 0;
+    while i < checksums::CHECKSUMS.len() {
+        let (other, size, sha256) = checksums::CHECKSUMS[i];
+        if str_eq(name, other) {
+            return Some((size, sha256));
+        }
+        i += 1;
+    }
+    None
+}
+
+const fn str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// [parse_sha256] for the checksum table, failing the build on a malformed entry.
+const fn parse_sha256_const(hex: &str) -> [u8; 32] {
+    const fn digit(c: u8) -> u8 {
+        match c {
+            b'0'..=b'9' => c - b'0',
+            b'a'..=b'f' => c - b'a' + 10,
+            _ => panic!("invalid SHA-256 in the checksum table"),
+        }
+    }
+    let hex = hex.as_bytes();
+    assert!(hex.len() == 64, "invalid SHA-256 in the checksum table");
+    let mut out = [0; 32];
+    let mut i = 0;
+    while i < 32 {
+        out[i] = digit(hex[2 * i]) * 16 + digit(hex[2 * i + 1]);
+        i += 1;
+    }
+    out
+}
+
+const CATALOG: &[Model] = &[
+    model("tiny", TINY, F16),
+    model("tiny.en", TINY_EN, F16),
+    model("tiny-q5_1", TINY, Q5_1),
+    model("tiny.en-q5_1", TINY_EN, Q5_1),
+    model("tiny-q8_0", TINY, Q8_0),
+    model("base", BASE, F16),
+    model("base.en", BASE_EN, F16),
+    model("base-q5_1", BASE, Q5_1),
+    model("base.en-q5_1", BASE_EN, Q5_1),
+    model("base-q8_0", BASE, Q8_0),
+    model("small", SMALL, F16),
+    model("small.en", SMALL_EN, F16),
+    model("small.en-tdrz", SMALL_EN, F16),
+    model("small-q5_1", SMALL, Q5_1),
+    model("small.en-q5_1", SMALL_EN, Q5_1),
+    model("small-q8_0", SMALL, Q8_0),
+    model("medium", MEDIUM, F16),
+    model("medium.en", MEDIUM_EN, F16),
+    model("medium-q5_0", MEDIUM, Q5_0),
+    model("medium.en-q5_0", MEDIUM_EN, Q5_0),
+    model("medium-q8_0", MEDIUM, Q8_0),
+    model("large-v1", LARGE_V1, F16),
+    model("large-v2", LARGE_V2, F16),
+    model("large-v2-q5_0", LARGE_V2, Q5_0),
+    model("large-v2-q8_0", LARGE_V2, Q8_0),
+    model("large-v3", LARGE_V3, F16),
+    model("large-v3-q5_0", LARGE_V3, Q5_0),
+    model("large-v3-turbo", LARGE_V3_TURBO, F16),
+    model("large-v3-turbo-q5_0", LARGE_V3_TURBO, Q5_0),
+    model("large-v3-turbo-q8_0", LARGE_V3_TURBO, Q8_0),
+];
+
+/// One of the standard ggml Whisper models, as published for whisper.cpp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Model {
+    name: &'static str,
+    arch: Arch,
+    ftype: i32,
+    sha256: Option<[u8; 32]>,
+    size: Option<u64>,
+}
+
+impl Model {
+    /// All known models, from `tiny` to `large-v3-turbo`, including the quantized variants.
+    pub fn all() -> &'static [Model] {
+        CATALOG
+    }
+
+    /// Look up a model by its name, such as `base.en` or `large-v3-turbo-q5_0`.
+    pub fn by_name(name: &str) -> Option<Model> {
+        CATALOG.iter().find(|model| model.name == name).copied()
+    }
+
+    /// Name of the model, such as `base.en`.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Name of the model file, such as `ggml-base.en.bin`.
+    pub fn file_name(&self) -> String {
+        format!("ggml-{}.bin", self.name)
+    }
+
+    /// Does this model support multiple languages?
+    pub fn is_multilingual(&self) -> bool {
+        self.arch.multilingual
+    }
+
+    /// The alignment heads preset for DTW token level timestamps with this model,
+    /// see [crate::DtwMode::ModelPreset].
+    pub fn dtw_preset(&self) -> DtwModelPreset {
+        self.arch.dtw_preset
+    }
+
+    /// The expected SHA-256 of the model file: the published one,
+    /// unless overridden with [Model::with_sha256].
+    pub fn sha256(&self) -> Option<[u8; 32]> {
+        self.sha256
+    }
+
+    /// The expected size of the model file in bytes: the published one,
+    /// unless overridden with [Model::with_size].
+    pub fn size(&self) -> Option<u64> {
+        self.size
+    }
+
+    /// Require the model file to have this SHA-256, given as 64 hexadecimal digits,
+    /// instead of the published one.
+    ///
+    /// # Errors
+    /// [ModelError::InvalidChecksum] if `hex` is not a valid SHA-256.
+    pub fn with_sha256(mut self, hex: &str) -> Result<Self, ModelError> {
+        self.sha256 = Some(parse_sha256(hex).ok_or(ModelError::InvalidChecksum)?);
+        Ok(self)
+    }
+
+    /// Require the model file to be `size` bytes long, instead of the published size.
+    pub fn with_size(mut self, size: u64) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    /// Check that the file at `path` is this model.
+    ///
+    /// The header is checked against the architecture and weight type of the model,
+    /// and the size and SHA-256 against the published ones or their overrides.
+    /// Hashing reads the whole file, which takes a few seconds for the large models.
+    /// Without a known SHA-256 only the header is checked, and a warning is logged.
+    ///
+    /// # Errors
+    /// * [ModelError::Io] if the file cannot be read
+    /// * [ModelError::SizeMismatch], [ModelError::HeaderMismatch] or
+    ///   [ModelError::ChecksumMismatch] if it is not this model
+    pub fn verify(&self, path: &Path) -> Result<(), ModelError> {
+        if let Some(expected) = self.size {
+            let actual = std::fs::metadata(path)?.len();
+            if actual != expected {
+                return Err(ModelError::SizeMismatch { expected, actual });
+            }
+        }
+
+        let info = ModelInfo::read(BufReader::new(File::open(path)?))?;
+        let fields = [
+            ("n_audio_layer", self.arch.n_audio_layer, info.n_audio_layer),
+            ("n_text_layer", self.arch.n_text_layer, info.n_text_layer),
+            ("n_mels", self.arch.n_mels, info.n_mels),
+            (
+                "multilingual",
+                self.arch.multilingual as i32,
+                info.is_multilingual() as i32,
+            ),
+            ("ftype", self.ftype, info.ftype),
+        ];
+        for (field, expected, actual) in fields {
+            if actual != expected {
+                return Err(ModelError::HeaderMismatch {
+                    field,
+                    expected,
+                    actual,
+                });
+            }
+        }
+
+        let Some(expected) = self.sha256 else {
+            generic_warn!(
+                "{}: no SHA-256 known for {}, only its header was checked",
+                path.display(),
+                self
+            );
+            return Ok(());
+        };
+        let actual = sha256_file(path)?;
+        if actual != expected {
+            return Err(ModelError::ChecksumMismatch {
+                expected: to_hex(&expected),
+                actual: to_hex(&actual),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Model {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name)
+    }
+}
+
+/// A directory holding model files under their standard names, such as `ggml-base.en.bin`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelCache {
+    dir: PathBuf,
+}
+
+impl ModelCache {
+    /// Use `dir` as the cache directory.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Use the directory in the `WHISPER_RS_MODEL_DIR` environment variable if set,
+    /// or else `whisper-rs/models` in the user's cache directory:
+    /// * `$XDG_CACHE_HOME` or `~/.cache` on Linux and other Unix systems
+    /// * `~/Library/Caches` on macOS
+    /// * `%LOCALAPPDATA%` on Windows
+    ///
+    /// # Errors
+    /// [ModelError::NoCacheDir] if neither is set.
+    pub fn from_env() -> Result<Self, ModelError> {
+        cache_dir_from(|key| env::var_os(key))
+            .map(Self::new)
+            .ok_or(ModelError::NoCacheDir)
+    }
+
+    /// The cache directory.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Where `model` is stored in the cache, whether it exists or not.
+    pub fn path(&self, model: &Model) -> PathBuf {
+        self.dir.join(model.file_name())
+    }
+
+    /// Find `model` in the cache and check it with [Model::verify].
+    ///
+    /// # Returns
+    /// The path to the verified model file.
+    ///
+    /// # Errors
+    /// [ModelError::NotFound] if the model is not in the cache,
+    /// or any error of [Model::verify].
+    pub fn verify(&self, model: &Model) -> Result<PathBuf, ModelError> {
+        let path = self.path(model);
+        if !path.is_file() {
+            return Err(ModelError::NotFound(path));
+        }
+        model.verify(&path)?;
+        Ok(path)
+    }
+
+    /// Verify `model` with [ModelCache::verify], then load it.
+    pub fn load(
+        &self,
+        model: &Model,
+        parameters: WhisperContextParameters,
+    ) -> Result<WhisperContext, ModelError> {
+        let path = self.verify(model)?;
+        let reader = BufReader::with_capacity(1 << 20, File::open(path)?);
+        Ok(WhisperContext::new_from_reader(reader, parameters)?)
+    }
+}
+
+/// Resolve the cache directory, reading environment variables through `var`.
+fn cache_dir_from(var: impl Fn(&str) -> Option<OsString>) -> Option<PathBuf> {
+    let var = |key: &str| {
+        var(key)
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+    };
+    if let Some(dir) = var(MODEL_DIR_ENV) {
+        return Some(dir);
+    }
+
+    let base = if cfg!(windows) {
+        var("LOCALAPPDATA")?
+    } else if cfg!(target_os = "macos") {
+        var("HOME")?.join("Library").join("Caches")
+    } else {
+        // relative paths are invalid per the XDG spec and must be ignored
+        match var("XDG_CACHE_HOME").filter(|dir| dir.is_absolute()) {
+            Some(dir) => dir,
+            None => var("HOME")?.join(".cache"),
+        }
+    };
+    Some(base.join("whisper-rs").join("models"))
+}
+
+pub(crate) fn sha256_file(path: &Path) -> io::Result<[u8; 32]> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hasher.finalize().into())
+}
+
+fn parse_sha256(hex: &str) -> Option<[u8; 32]> {
+    let hex = hex.as_bytes();
+    if hex.len() != 64 {
+        return None;
+    }
+    let mut out = [0; 32];
+    for (byte, pair) in out.iter_mut().zip(hex.chunks_exact(2)) {
+        let digit = |c: u8| (c as char).to_digit(16);
+        *byte = (digit(pair[0])? * 16 + digit(pair[1])?) as u8;
+    }
+    Some(out)
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Errors that can occur while finding, verifying or loading a model.
+#[derive(Debug)]
+pub enum ModelError {
+    /// No cache directory is configured, see [ModelCache::from_env].
+    NoCacheDir,
+    /// The model is not in the cache, at the given path.
+    NotFound(PathBuf),
+    /// A checksum passed to [Model::with_sha256] is not 64 hexadecimal digits.
+    InvalidChecksum,
+    /// Reading the model file failed.
+    Io(io::Error),
+    /// The model file does not have a valid header.
+    Header(ModelInfoError),
+    /// The model file does not have the expected size.
+    SizeMismatch { expected: u64, actual: u64 },
+    /// A value in the header of the model file does not match the model.
+    HeaderMismatch {
+        field: &'static str,
+        expected: i32,
+        actual: i32,
+    },
+    /// The SHA-256 of the model file does not match the expected one.
+    ChecksumMismatch { expected: String, actual: String },
+    /// whisper.cpp failed to load the model.
+    Whisper(WhisperError),
+    /// Downloading the model failed.
+    #[cfg(feature = "download")]
+    Download(Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl From<io::Error> for ModelError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<ModelInfoError> for ModelError {
+    fn from(e: ModelInfoError) -> Self {
+        match e {
+            ModelInfoError::Io(e) => Self::Io(e),
+            e => Self::Header(e),
+        }
+    }
+}
+
+impl From<WhisperError> for ModelError {
+    fn from(e: WhisperError) -> Self {
+        Self::Whisper(e)
+    }
+}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModelError::NoCacheDir => write!(
+                f,
+                "No model cache directory, set {} or HOME.",
+                MODEL_DIR_ENV
+            ),
+            ModelError::NotFound(path) => write!(f, "Model not found at {}.", path.display()),
+            ModelError::InvalidChecksum => {
+                write!(f, "Invalid SHA-256, expected 64 hexadecimal digits.")
+            }
+            ModelError::Io(e) => write!(f, "Failed to read model: {}", e),
+            ModelError::Header(e) => e.fmt(f),
+            ModelError::SizeMismatch { expected, actual } => write!(
+                f,
+                "Model file is {} bytes, expected {} bytes.",
+                actual, expected
+            ),
+            ModelError::HeaderMismatch {
+                field,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Model file has {} {}, expected {}.",
+                field, actual, expected
+            ),
+            ModelError::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "Model file has SHA-256 {}, expected {}.",
+                actual, expected
+            ),
+            ModelError::Whisper(e) => e.fmt(f),
+            #[cfg(feature = "download")]
+            ModelError::Download(e) => write!(f, "Failed to download model: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ModelError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ModelError::Io(e) => Some(e),
+            ModelError::Header(e) => Some(e),
+            ModelError::Whisper(e) => Some(e),
+            #[cfg(feature = "download")]
+            ModelError::Download(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// The start of a model file with the header of `model`, without any tensors.
+    pub(crate) fn fake_model(model: &Model) -> Vec<u8> {
+        let arch = model.arch;
+        let n_vocab = if arch.multilingual { 51865 } else { 51864 };
+        let mut out = Vec::new();
+        out.extend(0x67676d6cu32.to_le_bytes());
+        for value in [
+            n_vocab,
+            1500,
+            384,
+            6,
+            arch.n_audio_layer,
+            448,
+            384,
+            6,
+            arch.n_text_layer,
+            arch.n_mels,
+            // quantized models are at quantization version 2
+            if model.ftype == F16 {
+                model.ftype
+            } else {
+                model.ftype + 2000
+            },
+        ] {
+            out.extend(i32::to_le_bytes(value));
+        }
+        out.extend(arch.n_mels.to_le_bytes());
+        out.extend(1i32.to_le_bytes());
+        out.extend(vec![0; arch.n_mels as usize * 4]);
+        // empty vocabulary
+        out.extend(0i32.to_le_bytes());
+        out
+    }
+
+    /// `model`, expecting the size and SHA-256 of `bytes` instead of the published ones.
+    pub(crate) fn pin_to(model: Model, bytes: &[u8]) -> Model {
+        model
+            .with_size(bytes.len() as u64)
+            .with_sha256(&to_hex(&Sha256::digest(bytes)))
+            .unwrap()
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("whisper-rs-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_catalog() {
+        assert_eq!(Model::all().len(), 30);
+        let model = Model::by_name("large-v3-turbo-q5_0").unwrap();
+        assert_eq!(model.file_name(), "ggml-large-v3-turbo-q5_0.bin");
+        assert!(model.is_multilingual());
+        assert_eq!(model.dtw_preset(), DtwModelPreset::LargeV3Turbo);
+        assert!(!Model::by_name("base.en").unwrap().is_multilingual());
+        assert!(Model::by_name("huge").is_none());
+
+        let mut names = Model::all().iter().map(Model::name).collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), Model::all().len());
+    }
+
+    #[test]
+    #[ignore = "the checksum table is empty until scripts/update-model-checksums.py is run"]
+    fn test_catalog_is_pinned() {
+        for model in Model::all() {
+            assert!(model.size().is_some(), "{} has no size", model);
+            assert!(model.sha256().is_some(), "{} has no SHA-256", model);
+        }
+    }
+
+    #[test]
+    fn test_checksum_table() {
+        for &(name, size, sha256) in checksums::CHECKSUMS {
+            let model = Model::by_name(name).unwrap();
+            assert_eq!(model.size(), Some(size));
+            assert_eq!(model.sha256(), parse_sha256(sha256));
+        }
+        assert_eq!(
+            parse_sha256_const("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"),
+            <[u8; 32]>::from(Sha256::digest(b"abc"))
+        );
+        assert!(str_eq("tiny", "tiny"));
+        assert!(!str_eq("tiny", "tiny.en"));
+    }
+
+    #[test]
+    fn test_parse_sha256() {
+        // SHA-256 of "abc"
+        let hex = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        let parsed = parse_sha256(hex).unwrap();
+        assert_eq!(parsed[0], 0xba);
+        assert_eq!(parsed[31], 0xad);
+        assert_eq!(parsed, <[u8; 32]>::from(Sha256::digest(b"abc")));
+        assert_eq!(to_hex(&parsed), hex);
+        assert_eq!(parse_sha256(&hex.to_uppercase()), Some(parsed));
+
+        assert!(parse_sha256(&hex[1..]).is_none());
+        assert!(parse_sha256(&hex.replace('a', "g")).is_none());
+        assert!(matches!(
+            Model::by_name("tiny").unwrap().with_sha256("abc"),
+            Err(ModelError::InvalidChecksum)
+        ));
+    }
+
+    #[test]
+    fn test_cache_dir() {
+        let resolve = |vars: &[(&str, &str)]| {
+            let vars = vars
+                .iter()
+                .map(|&(k, v)| (k.to_string(), OsString::from(v)))
+                .collect::<HashMap<_, _>>();
+            cache_dir_from(|key| vars.get(key).cloned())
+        };
+
+        assert_eq!(
+            resolve(&[(MODEL_DIR_ENV, "/models"), ("HOME", "/home/me")]),
+            Some(PathBuf::from("/models"))
+        );
+        assert_eq!(resolve(&[(MODEL_DIR_ENV, "")]), None);
+        if cfg!(all(unix, not(target_os = "macos"))) {
+            assert_eq!(
+                resolve(&[("XDG_CACHE_HOME", "/cache"), ("HOME", "/home/me")]),
+                Some(PathBuf::from("/cache/whisper-rs/models"))
+            );
+            assert_eq!(
+                resolve(&[("XDG_CACHE_HOME", "cache"), ("HOME", "/home/me")]),
+                Some(PathBuf::from("/home/me/.cache/whisper-rs/models"))
+            );
+        }
+    }
+
+    #[test]
+    fn test_verify() {
+        let cache = ModelCache::new(temp_dir("verify"));
+        let model = Model::by_name("base.en-q5_1").unwrap();
+        assert!(matches!(cache.verify(&model), Err(ModelError::NotFound(_))));
+
+        let bytes = fake_model(&model);
+        let model = pin_to(model, &bytes);
+        std::fs::write(cache.path(&model), &bytes).unwrap();
+        assert_eq!(cache.verify(&model).unwrap(), cache.path(&model));
+
+        // the file of another model
+        std::fs::write(
+            cache.path(&model),
+            fake_model(&Model::by_name("base-q5_1").unwrap()),
+        )
+        .unwrap();
+        assert!(matches!(
+            cache.verify(&model),
+            Err(ModelError::HeaderMismatch {
+                field: "multilingual",
+                ..
+            })
+        ));
+        std::fs::write(cache.path(&model), &bytes[..bytes.len() - 1]).unwrap();
+        assert!(matches!(
+            cache.verify(&model),
+            Err(ModelError::SizeMismatch { .. })
+        ));
+        let truncated = model.with_size(bytes.len() as u64 - 1);
+        assert!(matches!(cache.verify(&truncated), Err(ModelError::Io(_))));
+        std::fs::write(cache.path(&model), &bytes).unwrap();
+
+        let sha256 = to_hex(&Sha256::digest(&bytes));
+        let pinned = model.with_sha256(&"0".repeat(64)).unwrap();
+        assert!(matches!(
+            cache.verify(&pinned),
+            Err(ModelError::ChecksumMismatch { actual, .. }) if actual == sha256
+        ));
+
+        std::fs::remove_dir_all(cache.dir()).unwrap();
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "test-with-tiny-model")]
+mod test_with_tiny_model {
+    use super::*;
+
+    // These tests expect that the tiny.en model has been downloaded
+    // using the script `sys/whisper.cpp/models/download-ggml-model.sh tiny.en`
+
+    #[test]
+    fn test_load_from_cache() {
+        let cache = ModelCache::new("./sys/whisper.cpp/models");
+        let model = Model::by_name("tiny.en").unwrap();
+        let ctx = cache
+            .load(&model, WhisperContextParameters::default())
+            .expect("Download the ggml-tiny.en model using 'sys/whisper.cpp/models/download-ggml-model.sh tiny.en'");
+        assert!(!ctx.is_multilingual());
+    }
+}
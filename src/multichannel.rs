@@ -0,0 +1,263 @@
+//! Transcribing each channel of a recording separately, e.g. the agent and customer sides of a call.
+
+use std::borrow::Cow;
+
+use crate::output::SegmentSource;
+use crate::{split_channels, FullParams, Segment, Transcript, WhisperContext, WhisperError};
+
+/// Results of [transcribe_channels]: one transcript per channel, labelled with a speaker name.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Conversation {
+    /// Speaker name of each channel, by channel index.
+    ///
+    /// Defaults to `Channel 0`, `Channel 1` and so on.
+    pub speakers: Vec<String>,
+    /// Transcript of each channel, by channel index.
+    pub transcripts: Vec<Transcript>,
+}
+
+/// A single segment of a [Conversation], along with who said it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Turn<'a> {
+    /// Index of the channel the segment was transcribed from.
+    pub channel: usize,
+    /// Speaker name of the channel.
+    pub speaker: &'a str,
+    /// The segment itself. Timestamps are relative to the start of the recording.
+    pub segment: &'a Segment,
+}
+
+impl Conversation {
+    /// Build a conversation out of one transcript per channel, with the default speaker names.
+    pub fn new(transcripts: Vec<Transcript>) -> Self {
+        Self {
+            speakers: (0..transcripts.len())
+                .map(|c| format!("Channel {}", c))
+                .collect(),
+            transcripts,
+        }
+    }
+
+    /// Replace the speaker names, in channel order.
+    ///
+    /// Channels without a name in `speakers` keep their current one,
+    /// and names beyond the number of channels are ignored.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use whisper_rs::{transcribe_channels, FullParams, SamplingStrategy, WhisperContext};
+    /// # fn run(ctx: &WhisperContext, stereo: &[f32]) -> Result<(), whisper_rs::WhisperError> {
+    /// let params = FullParams::new(SamplingStrategy::default());
+    /// let conversation =
+    ///     transcribe_channels(ctx, &params, stereo, 2)?.with_speakers(["Agent", "Customer"]);
+    /// print!("{}", conversation.text());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_speakers<I, T>(mut self, speakers: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        for (current, name) in self.speakers.iter_mut().zip(speakers) {
+            *current = name.into();
+        }
+        self
+    }
+
+    /// All segments of all channels, ordered by start time.
+    ///
+    /// Segments starting at the same time are ordered by channel.
+    pub fn turns(&self) -> Vec<Turn<'_>> {
+        let mut turns = self
+            .transcripts
+            .iter()
+            .enumerate()
+            .flat_map(|(channel, transcript)| {
+                transcript.segments.iter().map(move |segment| Turn {
+                    channel,
+                    speaker: &self.speakers[channel],
+                    segment,
+                })
+            })
+            .collect::<Vec<_>>();
+        // stable, so each channel keeps its own order and ties go to the lower channel
+        turns.sort_by_key(|turn| turn.segment.t0);
+        turns
+    }
+
+    /// Format the conversation as a script, one `Speaker: text` line per segment.
+    ///
+    /// Segments without any text are skipped.
+    pub fn text(&self) -> String {
+        let mut out = String::new();
+        for turn in self.turns() {
+            let text = turn.segment.text.trim();
+            if !text.is_empty() {
+                out.push_str(turn.speaker);
+                out.push_str(": ");
+                out.push_str(text);
+                out.push('\n');
+            }
+        }
+        out
+    }
+}
+
+/// Segments of all channels ordered by start time,
+/// with the speaker name prepended to the text as `Speaker: text`.
+impl SegmentSource for Conversation {
+    fn segments(&self) -> Result<Cow<'_, [Segment]>, WhisperError> {
+        Ok(Cow::Owned(
+            self.turns()
+                .into_iter()
+                .map(|turn| Segment {
+                    text: format!("{}: {}", turn.speaker, turn.segment.text.trim_start()),
+                    ..turn.segment.clone()
+                })
+                .collect(),
+        ))
+    }
+}
+
+/// Transcribe each channel of interleaved audio with its own [crate::WhisperState].
+///
+/// This is meant for recordings where every speaker has their own channel,
+/// such as call recordings with the agent on the left and the customer on the right.
+/// Unlike the stereo energy diarization of whisper.cpp's `--diarize` flag,
+/// each channel is transcribed on its own, so overlapping speech is not lost.
+///
+/// The channels are transcribed one after another, each with a copy of `params`.
+///
+/// # Arguments
+/// * `ctx` - The context to create the states from.
+/// * `params` - Parameters used for every channel.
+/// * `samples` - Interleaved 32-bit floating point PCM audio at 16 kHz.
+/// * `channels` - The number of channels in `samples`.
+///
+/// # Errors
+/// * if `samples` cannot be split into `channels` channels, see [split_channels]
+/// * if creating a state or transcribing any of the channels fails
+pub fn transcribe_channels(
+    ctx: &WhisperContext,
+    params: &FullParams,
+    samples: &[f32],
+    channels: usize,
+) -> Result<Conversation, WhisperError> {
+    let mut transcripts = Vec::with_capacity(channels);
+    for track in split_channels(samples, channels)? {
+        let mut state = ctx.create_state()?;
+        state.full(params.clone(), &track)?;
+        transcripts.push(state.transcript()?);
+    }
+    Ok(Conversation::new(transcripts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::write_srt;
+    use crate::test_util::{segment, transcript};
+
+    fn sample_conversation() -> Conversation {
+        Conversation::new(vec![
+            transcript(vec![
+                segment(0, 150, " Thanks for calling, how can I help?"),
+                segment(400, 520, " Sure, one moment."),
+                segment(900, 1000, " Done."),
+            ]),
+            transcript(vec![
+                segment(180, 390, " Hi, I'd like to cancel my order."),
+                segment(400, 450, " Thanks."),
+                segment(950, 990, "  "),
+            ]),
+        ])
+    }
+
+    #[test]
+    fn test_turns_are_interleaved() {
+        let conversation = sample_conversation();
+        let turns = conversation.turns();
+        let order = turns
+            .iter()
+            .map(|t| (t.channel, t.segment.t0))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            order,
+            [(0, 0), (1, 180), (0, 400), (1, 400), (0, 900), (1, 950)]
+        );
+        assert_eq!(turns[1].speaker, "Channel 1");
+    }
+
+    #[test]
+    fn test_with_speakers() {
+        let conversation = sample_conversation().with_speakers(["Agent"]);
+        assert_eq!(conversation.speakers, ["Agent", "Channel 1"]);
+        let conversation = conversation.with_speakers(["Agent", "Customer", "Nobody"]);
+        assert_eq!(conversation.speakers, ["Agent", "Customer"]);
+    }
+
+    #[test]
+    fn test_text() {
+        let conversation = sample_conversation().with_speakers(["Agent", "Customer"]);
+        assert_eq!(
+            conversation.text(),
+            "Agent: Thanks for calling, how can I help?\n\
+             Customer: Hi, I'd like to cancel my order.\n\
+             Agent: Sure, one moment.\n\
+             Customer: Thanks.\n\
+             Agent: Done.\n"
+        );
+    }
+
+    #[test]
+    fn test_segment_source() {
+        let conversation = sample_conversation().with_speakers(["Agent", "Customer"]);
+        let mut out = Vec::new();
+        write_srt(&conversation, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with(
+            "1\n00:00:00,000 --> 00:00:01,500\nAgent: Thanks for calling, how can I help?\n\n\
+             2\n00:00:01,800 --> 00:00:03,900\nCustomer: Hi, I'd like to cancel my order.\n"
+        ));
+    }
+
+    #[test]
+    fn test_empty_conversation() {
+        let conversation = Conversation::new(Vec::new());
+        assert!(conversation.turns().is_empty());
+        assert_eq!(conversation.text(), "");
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "test-with-tiny-model")]
+mod test_with_tiny_model {
+    use super::*;
+    use crate::test_util::{load_sample, MODEL_PATH};
+    use crate::{SamplingStrategy, WhisperContextParameters};
+
+    // These tests expect that the tiny.en model has been downloaded
+    // using the script `sys/whisper.cpp/models/download-ggml-model.sh tiny.en`
+
+    #[test]
+    fn test_transcribe_stereo_channels() {
+        let ctx = WhisperContext::new_with_params(MODEL_PATH, WhisperContextParameters::default())
+            .expect("Download the ggml-tiny.en model using 'sys/whisper.cpp/models/download-ggml-model.sh tiny.en'");
+        let sample = load_sample();
+        // speech on the left, silence on the right
+        let stereo = sample.iter().flat_map(|&x| [x, 0.0]).collect::<Vec<_>>();
+
+        let params = FullParams::new(SamplingStrategy::default());
+        let conversation = transcribe_channels(&ctx, &params, &stereo, 2)
+            .unwrap()
+            .with_speakers(["Agent", "Customer"]);
+        assert_eq!(conversation.transcripts.len(), 2);
+
+        let mut state = ctx.create_state().unwrap();
+        state.full(params, &sample).unwrap();
+        assert_eq!(conversation.transcripts[0], state.transcript().unwrap());
+        assert!(conversation.text().contains("Agent: "));
+    }
+}
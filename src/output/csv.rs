@@ -0,0 +1,47 @@
+use super::{segments_of, SegmentSource};
+use std::io::{self, Write};
+
+/// Quote a field for CSV output, doubling any embedded quotes.
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "\"\""))
+}
+
+/// Write segments as CSV, matching the output of whisper.cpp's `-ocsv` flag.
+///
+/// The output has a `start,end,text` header, followed by one row per segment
+/// with the start and end time in milliseconds and the quoted text.
+///
+/// # Errors
+/// Returns any error from the writer, or from fetching segments out of the source.
+pub fn write_csv<S, W>(source: &S, mut writer: W) -> io::Result<()>
+where
+    S: SegmentSource + ?Sized,
+    W: Write,
+{
+    let segments = segments_of(source)?;
+
+    writeln!(writer, "start,end,text")?;
+    for segment in segments.iter() {
+        writeln!(
+            writer,
+            "{},{},{}",
+            segment.t0 * 10,
+            segment.t1 * 10,
+            quote(&segment.text)
+        )?;
+    }
+
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::quote;
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote(" hello"), "\" hello\"");
+        assert_eq!(quote("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(quote("a, b"), "\"a, b\"");
+    }
+}
@@ -0,0 +1,338 @@
+use super::format_timestamp;
+use crate::{Segment, Token, Transcript, WhisperContext, WhisperError};
+use serde::{Deserialize, Serialize};
+use std::io::{self, Write};
+
+/// Options for [JsonOutput::new] and [write_json].
+#[derive(Debug, Clone, Default)]
+pub struct JsonOptions {
+    /// Path of the model file, reported in `params.model`.
+    pub model_path: String,
+    /// Language requested for the run, such as `en` or `auto`, reported in `params.language`.
+    ///
+    /// The detected language is reported in `result.language`.
+    pub language: String,
+    /// Whether the run translated to English, reported in `params.translate`.
+    pub translate: bool,
+    /// Include per-token data in each segment, like whisper.cpp's `-ojf` flag.
+    pub full: bool,
+}
+
+/// Full transcription output, using the same schema as whisper.cpp's `-oj` flag.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonOutput {
+    /// System information, as returned by [crate::print_system_info].
+    pub systeminfo: String,
+    /// The model used for the run.
+    pub model: JsonModel,
+    /// Parameters of the run.
+    pub params: JsonParams,
+    /// Information detected during the run.
+    pub result: JsonResult,
+    /// The transcribed segments.
+    pub transcription: Vec<JsonSegment>,
+}
+
+/// Type and hyperparameters of the model.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonModel {
+    /// Model type, such as `base`. Serialized as `type`.
+    #[serde(rename = "type")]
+    pub model_type: String,
+    /// Whether the model supports languages other than English.
+    pub multilingual: bool,
+    /// Number of tokens in the vocabulary.
+    pub vocab: i32,
+    /// Dimensions of the audio encoder.
+    pub audio: JsonModelDims,
+    /// Dimensions of the text decoder.
+    pub text: JsonModelDims,
+    /// Number of mel frequency bands.
+    pub mels: i32,
+    /// Data type of the weights, as a `ggml_ftype`.
+    pub ftype: i32,
+}
+
+/// Dimensions of the encoder or decoder of a model.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonModelDims {
+    /// Context length.
+    pub ctx: i32,
+    /// Width of the hidden state.
+    pub state: i32,
+    /// Number of attention heads.
+    pub head: i32,
+    /// Number of layers.
+    pub layer: i32,
+}
+
+/// Parameters of the run, see [JsonOptions].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonParams {
+    /// Path of the model file.
+    pub model: String,
+    /// Language requested for the run, such as `en` or `auto`.
+    pub language: String,
+    /// Whether the run translated to English.
+    pub translate: bool,
+}
+
+/// Information detected during the run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonResult {
+    /// Language of the transcript, either requested or detected.
+    pub language: String,
+}
+
+/// Start and end of a segment or token, formatted as `HH:MM:SS,mmm`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonTimestamps {
+    /// Start time.
+    pub from: String,
+    /// End time.
+    pub to: String,
+}
+
+/// Start and end of a segment or token, in milliseconds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonOffsets {
+    /// Start time.
+    pub from: i64,
+    /// End time.
+    pub to: i64,
+}
+
+/// A transcribed segment.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonSegment {
+    /// Start and end of the segment, formatted.
+    pub timestamps: JsonTimestamps,
+    /// Start and end of the segment, in milliseconds.
+    pub offsets: JsonOffsets,
+    /// Text of the segment.
+    pub text: String,
+    /// Tokens of the segment, only included with [JsonOptions::full].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tokens: Vec<JsonToken>,
+    /// Whether the next segment is predicted to be spoken by another speaker.
+    /// Only included if true.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub speaker_turn_next: bool,
+}
+
+/// A token of a segment.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonToken {
+    /// Text of the token.
+    pub text: String,
+    /// Start and end of the token, formatted.
+    pub timestamps: JsonTimestamps,
+    /// Start and end of the token, in milliseconds.
+    pub offsets: JsonOffsets,
+    /// Token ID.
+    pub id: i32,
+    /// Probability of the token.
+    pub p: f32,
+    /// DTW timestamp of the token in centiseconds, or -1 if DTW was not enabled.
+    pub t_dtw: i64,
+}
+
+impl JsonTimestamps {
+    fn new(t0: i64, t1: i64) -> Self {
+        Self {
+            from: format_timestamp(t0, ','),
+            to: format_timestamp(t1, ','),
+        }
+    }
+}
+
+impl JsonOffsets {
+    fn new(t0: i64, t1: i64) -> Self {
+        Self {
+            from: t0 * 10,
+            to: t1 * 10,
+        }
+    }
+}
+
+impl JsonModel {
+    /// Describe the model loaded into the given context.
+    pub fn new(ctx: &WhisperContext) -> Result<Self, WhisperError> {
+        Ok(Self {
+            model_type: ctx.model_type_readable()?,
+            multilingual: ctx.is_multilingual(),
+            vocab: ctx.model_n_vocab(),
+            audio: JsonModelDims {
+                ctx: ctx.model_n_audio_ctx(),
+                state: ctx.model_n_audio_state(),
+                head: ctx.model_n_audio_head(),
+                layer: ctx.model_n_audio_layer(),
+            },
+            text: JsonModelDims {
+                ctx: ctx.model_n_text_ctx(),
+                state: ctx.model_n_text_state(),
+                head: ctx.model_n_text_head(),
+                layer: ctx.model_n_text_layer(),
+            },
+            mels: ctx.model_n_mels(),
+            ftype: ctx.model_ftype(),
+        })
+    }
+}
+
+impl JsonSegment {
+    /// Convert a segment, optionally including its tokens.
+    pub fn new(segment: &Segment, full: bool) -> Self {
+        Self {
+            timestamps: JsonTimestamps::new(segment.t0, segment.t1),
+            offsets: JsonOffsets::new(segment.t0, segment.t1),
+            text: segment.text.clone(),
+            tokens: if full {
+                segment.tokens.iter().map(JsonToken::new).collect()
+            } else {
+                Vec::new()
+            },
+            speaker_turn_next: segment.speaker_turn_next,
+        }
+    }
+}
+
+impl JsonToken {
+    /// Convert a token.
+    pub fn new(token: &Token) -> Self {
+        Self {
+            text: token.text.clone(),
+            timestamps: JsonTimestamps::new(token.t0, token.t1),
+            offsets: JsonOffsets::new(token.t0, token.t1),
+            id: token.id,
+            p: token.p,
+            t_dtw: token.t_dtw,
+        }
+    }
+}
+
+impl JsonOutput {
+    /// Build the output for a transcript produced with the model loaded into `ctx`.
+    pub fn new(
+        ctx: &WhisperContext,
+        transcript: &Transcript,
+        options: &JsonOptions,
+    ) -> Result<Self, WhisperError> {
+        Ok(Self {
+            systeminfo: crate::print_system_info().to_string(),
+            model: JsonModel::new(ctx)?,
+            params: JsonParams {
+                model: options.model_path.clone(),
+                language: options.language.clone(),
+                translate: options.translate,
+            },
+            result: JsonResult {
+                language: transcript.language.clone().unwrap_or_default(),
+            },
+            transcription: transcript
+                .segments
+                .iter()
+                .map(|s| JsonSegment::new(s, options.full))
+                .collect(),
+        })
+    }
+}
+
+/// Write a transcript as JSON, matching the output of whisper.cpp's `-oj` flag
+/// (or `-ojf` if [JsonOptions::full] is set).
+///
+/// # Errors
+/// Returns any error from the writer, or from reading model information out of `ctx`.
+pub fn write_json<W: Write>(
+    ctx: &WhisperContext,
+    transcript: &Transcript,
+    mut writer: W,
+    options: &JsonOptions,
+) -> io::Result<()> {
+    let output = JsonOutput::new(ctx, transcript, options).map_err(io::Error::other)?;
+    serde_json::to_writer_pretty(&mut writer, &output)?;
+    writeln!(writer)?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::tests::sample_transcript;
+
+    #[test]
+    fn test_segment_schema() {
+        let transcript = sample_transcript();
+        let value = serde_json::to_value(JsonSegment::new(&transcript.segments[0], false)).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "timestamps": { "from": "00:00:00,000", "to": "00:00:02,500" },
+                "offsets": { "from": 0, "to": 2500 },
+                "text": " And so my fellow Americans,"
+            })
+        );
+
+        let value = serde_json::to_value(JsonSegment::new(&transcript.segments[1], false)).unwrap();
+        assert_eq!(value["speaker_turn_next"], serde_json::json!(true));
+    }
+
+    #[test]
+    fn test_token_schema() {
+        let token = Token {
+            id: 50363,
+            tid: 0,
+            text: "[_BEG_]".to_string(),
+            p: 0.5,
+            plog: 0.0,
+            pt: 0.0,
+            ptsum: 0.0,
+            t0: 0,
+            t1: 12,
+            t_dtw: -1,
+            vlen: 0.0,
+        };
+        let mut transcript = sample_transcript();
+        transcript.segments[0].tokens.push(token);
+
+        let value = serde_json::to_value(JsonSegment::new(&transcript.segments[0], true)).unwrap();
+        assert_eq!(
+            value["tokens"],
+            serde_json::json!([{
+                "text": "[_BEG_]",
+                "timestamps": { "from": "00:00:00,000", "to": "00:00:00,120" },
+                "offsets": { "from": 0, "to": 120 },
+                "id": 50363,
+                "p": 0.5,
+                "t_dtw": -1
+            }])
+        );
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "test-with-tiny-model")]
+mod test_with_tiny_model {
+    use super::*;
+    use crate::output::tests::sample_transcript;
+    use crate::test_util::MODEL_PATH;
+    use crate::WhisperContextParameters;
+
+    // These tests expect that the tiny.en model has been downloaded
+    // using the script `sys/whisper.cpp/models/download-ggml-model.sh tiny.en`
+
+    #[test]
+    fn test_requested_and_detected_language() {
+        let ctx = WhisperContext::new_with_params(MODEL_PATH, WhisperContextParameters::default())
+            .expect("Download the ggml-tiny.en model using 'sys/whisper.cpp/models/download-ggml-model.sh tiny.en'");
+        let options = JsonOptions {
+            model_path: MODEL_PATH.to_string(),
+            language: "auto".to_string(),
+            ..Default::default()
+        };
+        let output = JsonOutput::new(&ctx, &sample_transcript(), &options).unwrap();
+        assert_eq!(output.params.language, "auto");
+        assert_eq!(output.result.language, "en");
+        assert_eq!(output.model.model_type, "tiny");
+    }
+}
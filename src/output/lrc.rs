@@ -0,0 +1,53 @@
+use super::{segments_of, SegmentSource};
+use std::io::{self, Write};
+
+/// Format a timestamp in centiseconds as `mm:ss.xx`.
+///
+/// Minutes are not wrapped into hours, as LRC has no hour field.
+fn format_timestamp(t: i64) -> String {
+    let t = t.max(0);
+    format!("{:02}:{:02}.{:02}", t / 6000, t / 100 % 60, t % 100)
+}
+
+/// Write segments as LRC lyrics.
+///
+/// Each segment becomes a single `[mm:ss.xx]` line at its start time,
+/// with any line breaks in the text collapsed into spaces.
+/// Segments without any text are skipped.
+///
+/// # Errors
+/// Returns any error from the writer, or from fetching segments out of the source.
+pub fn write_lrc<S, W>(source: &S, mut writer: W) -> io::Result<()>
+where
+    S: SegmentSource + ?Sized,
+    W: Write,
+{
+    let segments = segments_of(source)?;
+
+    writeln!(writer, "[by:whisper-rs]")?;
+    for segment in segments.iter() {
+        let text = segment
+            .text
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        if text.is_empty() {
+            continue;
+        }
+        writeln!(writer, "[{}]{}", format_timestamp(segment.t0), text)?;
+    }
+
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format_timestamp;
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "00:00.00");
+        assert_eq!(format_timestamp(6_123), "01:01.23");
+        assert_eq!(format_timestamp(366_012), "61:00.12");
+    }
+}
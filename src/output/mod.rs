@@ -0,0 +1,201 @@
+//! Writers that turn transcription results into common subtitle, lyric and data formats.
+//!
+//! All writers accept anything implementing [SegmentSource],
+//! which includes both a [WhisperState] that has finished a call to [WhisperState::full],
+//! and an owned [Transcript].
+//!
+//! # Examples
+//! ```no_run
+//! # use whisper_rs::WhisperState;
+//! # fn write(state: &WhisperState) -> std::io::Result<()> {
+//! let file = std::fs::File::create("transcript.srt")?;
+//! whisper_rs::output::write_srt(state, std::io::BufWriter::new(file))?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod csv;
+#[cfg(feature = "json")]
+mod json;
+mod lrc;
+mod srt;
+mod vtt;
+
+pub use csv::write_csv;
+#[cfg(feature = "json")]
+pub use json::{
+    write_json, JsonModel, JsonModelDims, JsonOffsets, JsonOptions, JsonOutput, JsonParams,
+    JsonResult, JsonSegment, JsonTimestamps, JsonToken,
+};
+pub use lrc::write_lrc;
+pub use srt::write_srt;
+pub use vtt::{write_vtt, VttOptions};
+
+use crate::{Segment, Transcript, WhisperError, WhisperState};
+use std::borrow::Cow;
+use std::io;
+
+/// A source of segments for the writers in this module.
+pub trait SegmentSource {
+    /// Get all segments of this source, in order.
+    fn segments(&self) -> Result<Cow<'_, [Segment]>, WhisperError>;
+}
+
+impl SegmentSource for Transcript {
+    fn segments(&self) -> Result<Cow<'_, [Segment]>, WhisperError> {
+        Ok(Cow::Borrowed(&self.segments))
+    }
+}
+
+impl SegmentSource for [Segment] {
+    fn segments(&self) -> Result<Cow<'_, [Segment]>, WhisperError> {
+        Ok(Cow::Borrowed(self))
+    }
+}
+
+impl SegmentSource for WhisperState {
+    fn segments(&self) -> Result<Cow<'_, [Segment]>, WhisperError> {
+        WhisperState::segments(self)
+            .map(|s| s.to_segment())
+            .collect::<Result<Vec<_>, _>>()
+            .map(Cow::Owned)
+    }
+}
+
+/// Fetch the segments of a source, turning a [WhisperError] into an [io::Error]
+/// so writers only have a single error type.
+fn segments_of<S: SegmentSource + ?Sized>(source: &S) -> io::Result<Cow<'_, [Segment]>> {
+    source.segments().map_err(io::Error::other)
+}
+
+/// Split a timestamp in centiseconds into hours, minutes, seconds and milliseconds.
+///
+/// Negative timestamps are clamped to zero.
+fn split_timestamp(t: i64) -> (i64, i64, i64, i64) {
+    let msec = t.max(0) * 10;
+    let hours = msec / 3_600_000;
+    let minutes = msec / 60_000 % 60;
+    let seconds = msec / 1000 % 60;
+    (hours, minutes, seconds, msec % 1000)
+}
+
+/// Format a timestamp in centiseconds as `HH:MM:SS<separator>mmm`.
+fn format_timestamp(t: i64, separator: char) -> String {
+    let (hours, minutes, seconds, msec) = split_timestamp(t);
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        hours, minutes, seconds, separator, msec
+    )
+}
+
+/// Clean up segment text for use as a cue payload:
+/// surrounding whitespace is trimmed and empty lines are dropped,
+/// as an empty line terminates a cue in both SRT and WebVTT.
+fn cue_text(text: &str) -> String {
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{segment, transcript};
+
+    pub(super) fn sample_transcript() -> Transcript {
+        transcript(vec![
+            segment(0, 250, " And so my fellow Americans,"),
+            Segment {
+                speaker_turn_next: true,
+                ..segment(250, 471, " ask not what your country can do for you,")
+            },
+            segment(471, 1099, " ask what you can do <for> your country & more."),
+            segment(366_012, 366_154, "  Multi-line\n\n  text after an hour. "),
+        ])
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0, ','), "00:00:00,000");
+        assert_eq!(format_timestamp(1, '.'), "00:00:00.010");
+        assert_eq!(format_timestamp(6_123, ','), "00:01:01,230");
+        assert_eq!(format_timestamp(366_012, '.'), "01:01:00.120");
+        assert_eq!(format_timestamp(-5, ','), "00:00:00,000");
+    }
+
+    #[test]
+    fn test_cue_text() {
+        assert_eq!(cue_text(" hello "), "hello");
+        assert_eq!(cue_text("a\n\n b \n"), "a\nb");
+        assert_eq!(cue_text("   "), "");
+    }
+
+    #[test]
+    fn test_golden_srt() {
+        let mut out = Vec::new();
+        write_srt(&sample_transcript(), &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            include_str!("testdata/sample.srt")
+        );
+    }
+
+    #[test]
+    fn test_golden_vtt() {
+        let mut out = Vec::new();
+        write_vtt(&sample_transcript(), &mut out, &VttOptions::default()).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            include_str!("testdata/sample.vtt")
+        );
+    }
+
+    #[test]
+    fn test_golden_vtt_speakers() {
+        let mut out = Vec::new();
+        let options = VttOptions::default().with_speakers(["Agent", "Customer"]);
+        write_vtt(&sample_transcript(), &mut out, &options).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            include_str!("testdata/sample_speakers.vtt")
+        );
+    }
+
+    #[test]
+    fn test_golden_lrc() {
+        let mut out = Vec::new();
+        write_lrc(&sample_transcript(), &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            include_str!("testdata/sample.lrc")
+        );
+    }
+
+    #[test]
+    fn test_golden_csv() {
+        let mut out = Vec::new();
+        write_csv(&sample_transcript(), &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            include_str!("testdata/sample.csv")
+        );
+    }
+
+    #[test]
+    fn test_empty_transcript() {
+        let transcript = Transcript {
+            lang_id: -1,
+            language: None,
+            segments: Vec::new(),
+        };
+        let mut out = Vec::new();
+        write_srt(&transcript, &mut out).unwrap();
+        assert!(out.is_empty());
+
+        let mut out = Vec::new();
+        write_vtt(&transcript, &mut out, &VttOptions::default()).unwrap();
+        assert_eq!(out, b"WEBVTT\n\n");
+    }
+}
@@ -0,0 +1,38 @@
+use super::{cue_text, format_timestamp, segments_of, SegmentSource};
+use std::io::{self, Write};
+
+/// Write segments as SubRip (SRT) subtitles.
+///
+/// Cues are numbered starting at 1, and timestamps are formatted as `HH:MM:SS,mmm`.
+/// Segments without any text are skipped.
+///
+/// # Errors
+/// Returns any error from the writer, or from fetching segments out of the source.
+pub fn write_srt<S, W>(source: &S, mut writer: W) -> io::Result<()>
+where
+    S: SegmentSource + ?Sized,
+    W: Write,
+{
+    let segments = segments_of(source)?;
+
+    let mut index = 1;
+    for segment in segments.iter() {
+        let text = cue_text(&segment.text);
+        if text.is_empty() {
+            continue;
+        }
+
+        writeln!(writer, "{}", index)?;
+        writeln!(
+            writer,
+            "{} --> {}",
+            format_timestamp(segment.t0, ','),
+            format_timestamp(segment.t1, ',')
+        )?;
+        writeln!(writer, "{}", text)?;
+        writeln!(writer)?;
+        index += 1;
+    }
+
+    writer.flush()
+}
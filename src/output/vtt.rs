@@ -0,0 +1,93 @@
+use super::{cue_text, format_timestamp, segments_of, SegmentSource};
+use std::io::{self, Write};
+
+/// Options for [write_vtt].
+#[derive(Debug, Clone, Default)]
+pub struct VttOptions {
+    speakers: Vec<String>,
+}
+
+impl VttOptions {
+    /// Wrap each cue in a voice tag (`<v Name>`), switching to the next speaker
+    /// every time a segment predicts a speaker turn
+    /// (see [crate::FullParams::set_tdrz_enable]).
+    ///
+    /// Speakers are used in order, wrapping around after the last one.
+    /// Passing an empty list disables voice tags, which is the default.
+    pub fn with_speakers<I, T>(mut self, speakers: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.speakers = speakers.into_iter().map(Into::into).collect();
+        self
+    }
+}
+
+/// Escape text for use in a WebVTT cue payload.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Write segments as WebVTT subtitles.
+///
+/// Timestamps are formatted as `HH:MM:SS.mmm`, and `&`, `<` and `>` in the text are escaped.
+/// Segments without any text are skipped.
+///
+/// # Errors
+/// Returns any error from the writer, or from fetching segments out of the source.
+pub fn write_vtt<S, W>(source: &S, mut writer: W, options: &VttOptions) -> io::Result<()>
+where
+    S: SegmentSource + ?Sized,
+    W: Write,
+{
+    let segments = segments_of(source)?;
+
+    writeln!(writer, "WEBVTT")?;
+    writeln!(writer)?;
+
+    let mut speaker = 0;
+    for segment in segments.iter() {
+        let text = cue_text(&segment.text);
+        if !text.is_empty() {
+            writeln!(
+                writer,
+                "{} --> {}",
+                format_timestamp(segment.t0, '.'),
+                format_timestamp(segment.t1, '.')
+            )?;
+            match options.speakers.get(speaker) {
+                Some(name) => writeln!(writer, "<v {}>{}", escape(name), escape(&text))?,
+                None => writeln!(writer, "{}", escape(&text))?,
+            }
+            writeln!(writer)?;
+        }
+
+        if segment.speaker_turn_next && !options.speakers.is_empty() {
+            speaker = (speaker + 1) % options.speakers.len();
+        }
+    }
+
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::escape;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("a < b && c > d"), "a &lt; b &amp;&amp; c &gt; d");
+        assert_eq!(escape("-->"), "--&gt;");
+        assert_eq!(escape("plain"), "plain");
+    }
+}
@@ -0,0 +1,444 @@
+use std::f64::consts::PI;
+
+use whisper_rs_sys::WHISPER_SAMPLE_RATE;
+
+/// Half width of the kernel, in zero crossings of the sinc at the lower of the two rates.
+const ZERO_CROSSINGS: f64 = 32.0;
+/// Cutoff frequency, as a fraction of the lower Nyquist frequency.
+const ROLLOFF: f64 = 0.9;
+/// Shape of the Kaiser window. 9.0 gives roughly 90 dB of stopband attenuation.
+const KAISER_BETA: f64 = 9.0;
+/// Maximum number of coefficients to precompute. Rates with an unusual ratio need more
+/// phases than this, in which case the kernel is computed for every output sample instead.
+const MAX_TABLE_LEN: usize = 1 << 20;
+
+/// Resample mono audio to the 16 kHz whisper.cpp expects.
+///
+/// Uses a band-limited windowed-sinc filter, see [Resampler].
+/// For chunked input, use [Resampler] directly.
+///
+/// # Arguments
+/// * `samples` - Mono audio samples.
+/// * `input_rate` - Sample rate of `samples`, in Hz.
+///
+/// # Returns
+/// `ceil(samples.len() * 16000 / input_rate)` samples at 16 kHz.
+///
+/// # Panics
+/// * if `input_rate` is 0
+///
+/// # Examples
+/// ```
+/// # use whisper_rs::resample_to_whisper;
+/// let samples = [0.0f32; 44100];
+/// let resampled = resample_to_whisper(&samples, 44100);
+/// assert_eq!(resampled.len(), 16000);
+/// ```
+pub fn resample_to_whisper(samples: &[f32], input_rate: u32) -> Vec<f32> {
+    let mut resampler = Resampler::new(input_rate, WHISPER_SAMPLE_RATE);
+    let mut output = Vec::with_capacity(resampler.output_len(samples.len()));
+    resampler.process(samples, &mut output);
+    resampler.finish(&mut output);
+    output
+}
+
+/// Streaming band-limited resampler for mono audio.
+///
+/// Uses a Kaiser-windowed sinc kernel, with the cutoff just below the lower Nyquist frequency
+/// to prevent aliasing when downsampling and imaging when upsampling.
+/// The filter is linear-phase and centered, so the output is not delayed.
+///
+/// Feeding the input in chunks through [Resampler::process] and then calling [Resampler::finish]
+/// produces exactly the same output as resampling it in one go.
+#[derive(Debug, Clone)]
+pub struct Resampler {
+    input_rate: u32,
+    output_rate: u32,
+    /// Input samples per `phases` output samples, reduced by the GCD of both rates.
+    step: u64,
+    phases: u64,
+    /// Cutoff frequency, as a fraction of the input Nyquist frequency.
+    cutoff: f64,
+    /// Number of taps on each side of the output position.
+    half_taps: usize,
+    /// Precomputed kernel for each phase, `2 * half_taps` coefficients each.
+    table: Option<Vec<f32>>,
+    /// Kernel of the current output sample when there is no table, and the coefficients it is computed from.
+    scratch: Vec<f32>,
+    coefficients: Vec<f64>,
+    /// Pending input, starting at absolute input index `buffer_start`.
+    buffer: Vec<f32>,
+    buffer_start: i64,
+    n_input: u64,
+    n_output: u64,
+}
+
+impl Resampler {
+    /// Create a resampler from `input_rate` to `output_rate`, both in Hz.
+    ///
+    /// # Panics
+    /// * if either rate is 0
+    pub fn new(input_rate: u32, output_rate: u32) -> Self {
+        assert!(
+            input_rate > 0 && output_rate > 0,
+            "sample rates must be non-zero"
+        );
+        let gcd = gcd(input_rate as u64, output_rate as u64);
+        let step = input_rate as u64 / gcd;
+        let phases = output_rate as u64 / gcd;
+
+        let cutoff = ROLLOFF * (output_rate as f64 / input_rate as f64).min(1.0);
+        let half_taps = (ZERO_CROSSINGS / cutoff).ceil() as usize;
+
+        let mut resampler = Self {
+            input_rate,
+            output_rate,
+            step,
+            phases,
+            cutoff,
+            half_taps,
+            table: None,
+            scratch: vec![0.0; 2 * half_taps],
+            coefficients: vec![0.0; 2 * half_taps],
+            buffer: Vec::new(),
+            buffer_start: 0,
+            n_input: 0,
+            n_output: 0,
+        };
+        if (phases as usize).saturating_mul(2 * half_taps) <= MAX_TABLE_LEN {
+            let mut table = vec![0.0; phases as usize * 2 * half_taps];
+            for (phase, row) in table.chunks_exact_mut(2 * half_taps).enumerate() {
+                fill_kernel(
+                    phase as u64,
+                    phases,
+                    cutoff,
+                    half_taps,
+                    &mut resampler.coefficients,
+                    row,
+                );
+            }
+            resampler.table = Some(table);
+        }
+        resampler.reset();
+        resampler
+    }
+
+    /// Sample rate of the input, in Hz.
+    pub fn input_rate(&self) -> u32 {
+        self.input_rate
+    }
+
+    /// Sample rate of the output, in Hz.
+    pub fn output_rate(&self) -> u32 {
+        self.output_rate
+    }
+
+    /// Number of output samples produced for `input_len` input samples, once finished.
+    pub fn output_len(&self, input_len: usize) -> usize {
+        (input_len as u128 * self.phases as u128).div_ceil(self.step as u128) as usize
+    }
+
+    /// Resample a chunk of input, appending all output samples that can be computed so far.
+    ///
+    /// Output lags behind input by the width of the filter;
+    /// call [Resampler::finish] after the last chunk to get the rest.
+    pub fn process(&mut self, input: &[f32], output: &mut Vec<f32>) {
+        if self.is_passthrough() {
+            output.extend_from_slice(input);
+            return;
+        }
+        self.buffer.extend_from_slice(input);
+        self.n_input += input.len() as u64;
+        self.drain(u64::MAX, output);
+    }
+
+    /// Flush the remaining output, treating the input as followed by silence,
+    /// and reset the resampler so it can be reused for a new stream.
+    pub fn finish(&mut self, output: &mut Vec<f32>) {
+        if !self.is_passthrough() {
+            self.buffer.resize(self.buffer.len() + self.half_taps, 0.0);
+            let total = self.output_len(self.n_input as usize) as u64;
+            self.drain(total, output);
+        }
+        self.reset();
+    }
+
+    /// Discard any pending input and start a new stream.
+    pub fn reset(&mut self) {
+        // the kernel reaches half_taps - 1 samples before the first input sample
+        self.buffer.clear();
+        self.buffer.resize(self.half_taps - 1, 0.0);
+        self.buffer_start = -(self.half_taps as i64 - 1);
+        self.n_input = 0;
+        self.n_output = 0;
+    }
+
+    fn is_passthrough(&self) -> bool {
+        self.step == self.phases
+    }
+
+    /// Compute output samples until either `limit` samples have been produced
+    /// or the input runs out, then drop input that is no longer needed.
+    fn drain(&mut self, limit: u64, output: &mut Vec<f32>) {
+        let taps = 2 * self.half_taps;
+        let buffer_end = self.buffer_start + self.buffer.len() as i64;
+        while self.n_output < limit {
+            let (center, phase) = self.position(self.n_output);
+            let first = center - self.half_taps as i64 + 1;
+            if first + taps as i64 > buffer_end {
+                break;
+            }
+            let start = (first - self.buffer_start) as usize;
+            let input = &self.buffer[start..start + taps];
+            let kernel: &[f32] = match &self.table {
+                Some(table) => &table[phase as usize * taps..][..taps],
+                None => {
+                    fill_kernel(
+                        phase,
+                        self.phases,
+                        self.cutoff,
+                        self.half_taps,
+                        &mut self.coefficients,
+                        &mut self.scratch,
+                    );
+                    &self.scratch
+                }
+            };
+            output.push(input.iter().zip(kernel).map(|(x, h)| x * h).sum());
+            self.n_output += 1;
+        }
+
+        let (center, _) = self.position(self.n_output);
+        let needed = center - self.half_taps as i64 + 1;
+        let unused = (needed - self.buffer_start).clamp(0, self.buffer.len() as i64);
+        self.buffer.drain(..unused as usize);
+        self.buffer_start += unused;
+    }
+
+    /// Input sample at or before output sample `n`, and the phase of `n` between input samples.
+    fn position(&self, n: u64) -> (i64, u64) {
+        let position = n as u128 * self.step as u128;
+        let phases = self.phases as u128;
+        ((position / phases) as i64, (position % phases) as u64)
+    }
+}
+
+/// Fill `kernel` with the coefficients for an output sample `phase / phases` after an input sample,
+/// using `coefficients` as scratch space of the same length.
+fn fill_kernel(
+    phase: u64,
+    phases: u64,
+    cutoff: f64,
+    half_taps: usize,
+    coefficients: &mut [f64],
+    kernel: &mut [f32],
+) {
+    let offset = phase as f64 / phases as f64;
+    let half_width = ZERO_CROSSINGS / cutoff;
+    let mut sum = 0.0;
+    for (i, c) in coefficients.iter_mut().enumerate() {
+        // distance from the output position to input sample `center - half_taps + 1 + i`
+        let x = offset + half_taps as f64 - 1.0 - i as f64;
+        *c = cutoff * sinc(cutoff * x) * kaiser(x / half_width);
+        sum += *c;
+    }
+    // normalize so DC passes through unchanged at every phase
+    for (k, c) in kernel.iter_mut().zip(coefficients.iter()) {
+        *k = (c / sum) as f32;
+    }
+}
+
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-12 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+fn kaiser(r: f64) -> f64 {
+    if r.abs() >= 1.0 {
+        0.0
+    } else {
+        bessel_i0(KAISER_BETA * (1.0 - r * r).sqrt()) / bessel_i0(KAISER_BETA)
+    }
+}
+
+/// Zeroth-order modified Bessel function of the first kind.
+fn bessel_i0(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let half = x / 2.0;
+    for k in 1..64 {
+        term *= (half / k as f64) * (half / k as f64);
+        sum += term;
+        if term < sum * 1e-16 {
+            break;
+        }
+    }
+    sum
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const RATES: [u32; 8] = [8000, 11025, 16000, 22050, 32000, 44100, 48000, 96000];
+
+    /// Linear sweep from `f0` to `f1` Hz over `duration` seconds, sampled at `rate`.
+    fn sweep(rate: u32, f0: f64, f1: f64, duration: f64) -> Vec<f32> {
+        let n = (duration * rate as f64) as usize;
+        (0..n)
+            .map(|i| {
+                let t = i as f64 / rate as f64;
+                let phase = 2.0 * PI * (f0 * t + (f1 - f0) * t * t / (2.0 * duration));
+                (0.5 * phase.sin()) as f32
+            })
+            .collect()
+    }
+
+    fn sine(rate: u32, freq: f64, n: usize) -> Vec<f32> {
+        (0..n)
+            .map(|i| (0.5 * (2.0 * PI * freq * i as f64 / rate as f64).sin()) as f32)
+            .collect()
+    }
+
+    fn rms(samples: &[f32]) -> f64 {
+        (samples.iter().map(|&x| x as f64 * x as f64).sum::<f64>() / samples.len() as f64).sqrt()
+    }
+
+    /// Skip the edges, where the input is implicitly padded with silence.
+    fn interior(samples: &[f32]) -> &[f32] {
+        &samples[200..samples.len() - 200]
+    }
+
+    #[test]
+    fn test_output_length() {
+        for rate in RATES {
+            for len in [0, 1, 7, 160, 12345, 48000] {
+                let expected = (len as u64 * 16000).div_ceil(rate as u64) as usize;
+                let output = resample_to_whisper(&vec![0.25; len], rate);
+                assert_eq!(output.len(), expected, "rate {} len {}", rate, len);
+                assert_eq!(Resampler::new(rate, 16000).output_len(len), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_passthrough() {
+        let input = sweep(16000, 50.0, 7000.0, 0.5);
+        assert_eq!(resample_to_whisper(&input, 16000), input);
+    }
+
+    #[test]
+    fn test_dc_gain() {
+        for rate in RATES {
+            let output = resample_to_whisper(&vec![0.5; rate as usize], rate);
+            for &x in interior(&output) {
+                assert!((x - 0.5).abs() < 1e-5, "rate {}: {}", rate, x);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sweep_matches_analytic() {
+        for rate in RATES {
+            // stay below the cutoff of the lower rate, where the response is flat
+            let f1 = (0.4 * rate as f64).min(6500.0);
+            let output = resample_to_whisper(&sweep(rate, 50.0, f1, 1.0), rate);
+            let expected = sweep(16000, 50.0, f1, 1.0);
+            let error = interior(&output)
+                .iter()
+                .zip(interior(&expected))
+                .map(|(a, b)| a - b)
+                .collect::<Vec<_>>();
+            assert!(
+                rms(&error) < 1e-3,
+                "rate {}: rms error {}",
+                rate,
+                rms(&error)
+            );
+        }
+    }
+
+    #[test]
+    fn test_no_aliasing() {
+        // tones above 8 kHz must not fold back into the output
+        for (rate, freq) in [
+            (48000, 9000.0),
+            (48000, 12000.0),
+            (48000, 20000.0),
+            (44100, 10000.0),
+            (44100, 15999.0),
+            (96000, 40000.0),
+            (22050, 9000.0),
+        ] {
+            let output = resample_to_whisper(&sine(rate, freq, rate as usize), rate);
+            let level = 20.0 * (rms(interior(&output)) / (0.5 / 2f64.sqrt())).log10();
+            assert!(level < -70.0, "rate {} freq {}: {} dB", rate, freq, level);
+        }
+    }
+
+    #[test]
+    fn test_no_imaging() {
+        // upsampling must not create energy above the input Nyquist frequency
+        let output = resample_to_whisper(&sine(8000, 1000.0, 8000), 8000);
+        let expected = sine(16000, 1000.0, 16000);
+        let error = interior(&output)
+            .iter()
+            .zip(interior(&expected))
+            .map(|(a, b)| a - b)
+            .collect::<Vec<_>>();
+        assert!(rms(&error) < 1e-3, "rms error {}", rms(&error));
+    }
+
+    #[test]
+    fn test_chunked_matches_one_shot() {
+        for rate in [11025, 44100, 48000] {
+            let input = sweep(rate, 50.0, 6500.0, 0.5);
+            let expected = resample_to_whisper(&input, rate);
+
+            let mut resampler = Resampler::new(rate, 16000);
+            let mut output = Vec::new();
+            let mut chunk_len = 1;
+            let mut rest = input.as_slice();
+            while !rest.is_empty() {
+                let (chunk, tail) = rest.split_at(chunk_len.min(rest.len()));
+                resampler.process(chunk, &mut output);
+                rest = tail;
+                chunk_len = chunk_len * 3 % 997 + 1;
+            }
+            resampler.finish(&mut output);
+            assert_eq!(output, expected, "rate {}", rate);
+
+            // finish resets, so the resampler can be reused
+            let mut again = Vec::new();
+            resampler.process(&input, &mut again);
+            resampler.finish(&mut again);
+            assert_eq!(again, expected, "rate {}", rate);
+        }
+    }
+
+    #[test]
+    fn test_unusual_ratio_without_table() {
+        // 44099 and 16000 are coprime, so the kernel is computed on the fly
+        let resampler = Resampler::new(44099, 16000);
+        assert!(resampler.table.is_none());
+        let output = resample_to_whisper(&sweep(44099, 50.0, 6500.0, 0.5), 44099);
+        let expected = sweep(16000, 50.0, 6500.0, 0.5);
+        assert_eq!(output.len(), resampler.output_len(44099 / 2));
+        let error = interior(&output)
+            .iter()
+            .zip(interior(&expected))
+            .map(|(a, b)| a - b)
+            .collect::<Vec<_>>();
+        assert!(rms(&error) < 1e-3, "rms error {}", rms(&error));
+    }
+}
@@ -0,0 +1,380 @@
+//! Real-time transcription over a sliding window of pushed audio.
+
+use std::ffi::c_int;
+
+use crate::{FullParams, Segment, WhisperError, WhisperState, WHISPER_SAMPLE_RATE};
+
+/// Configuration of the sliding window used by [StreamingTranscriber].
+///
+/// Mirrors the `--step`, `--length` and `--keep` options of whisper.cpp's `stream` example.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamingConfig {
+    /// How much new audio to collect before re-running the model, in milliseconds.
+    ///
+    /// Defaults to 3000.
+    pub step_ms: u32,
+    /// Length of the window the model is run on, in milliseconds.
+    /// Once this much audio has been transcribed, the results are committed as stable.
+    ///
+    /// Defaults to 10000.
+    pub length_ms: u32,
+    /// How much audio from the end of a committed window to keep at the start of the next one,
+    /// in milliseconds. This helps with words cut off at the window boundary.
+    ///
+    /// Defaults to 200.
+    pub keep_ms: u32,
+    /// Pass the tokens of the last committed window as the prompt for the next one.
+    ///
+    /// Defaults to true.
+    pub use_prompt: bool,
+}
+
+impl Default for StreamingConfig {
+    fn default() -> Self {
+        Self {
+            step_ms: 3000,
+            length_ms: 10000,
+            keep_ms: 200,
+            use_prompt: true,
+        }
+    }
+}
+
+/// An update produced by [StreamingTranscriber].
+///
+/// All timestamps are absolute, in centiseconds since the first sample pushed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamingEvent {
+    /// The current best guess for the audio that has not been committed yet.
+    /// Replaces any previous tentative segments.
+    Tentative(Vec<Segment>),
+    /// Segments that have been committed and will not change anymore.
+    Stable(Vec<Segment>),
+}
+
+/// A window of audio ready to be run through the model.
+#[derive(Debug)]
+struct Window {
+    samples: Vec<f32>,
+    /// Absolute position of the first sample in the window.
+    start: u64,
+    /// Whether the results for this window should be committed.
+    commit: bool,
+}
+
+/// Sliding window bookkeeping, mirroring whisper.cpp's `stream` example.
+#[derive(Debug)]
+struct SlidingWindow {
+    n_samples_step: usize,
+    n_samples_len: usize,
+    n_samples_keep: usize,
+    n_new_line: u32,
+    n_iter: u32,
+    /// Samples pushed since the last window was produced.
+    pending: Vec<f32>,
+    /// Audio of the last window, trimmed to what is kept after a commit.
+    old: Vec<f32>,
+    /// Absolute position one past the last sample in `old`.
+    old_end: u64,
+}
+
+fn ms_to_samples(ms: u32) -> usize {
+    (ms as u64 * WHISPER_SAMPLE_RATE as u64 / 1000) as usize
+}
+
+impl SlidingWindow {
+    fn new(config: &StreamingConfig) -> Self {
+        let step_ms = config.step_ms.max(1);
+        let length_ms = config.length_ms.max(step_ms);
+        Self {
+            n_samples_step: ms_to_samples(step_ms),
+            n_samples_len: ms_to_samples(length_ms),
+            n_samples_keep: ms_to_samples(config.keep_ms.min(step_ms)),
+            n_new_line: (length_ms / step_ms).saturating_sub(1).max(1),
+            n_iter: 0,
+            pending: Vec::new(),
+            old: Vec::new(),
+            old_end: 0,
+        }
+    }
+
+    fn push(&mut self, samples: &[f32]) {
+        self.pending.extend_from_slice(samples);
+    }
+
+    /// Produce the next window, if a full step of audio is pending (or if `flush` is set).
+    fn next_window(&mut self, flush: bool) -> Option<Window> {
+        let n_new = if self.pending.len() >= self.n_samples_step {
+            self.n_samples_step
+        } else if flush && !self.pending.is_empty() {
+            self.pending.len()
+        } else {
+            return None;
+        };
+
+        // take as much old audio as fits in the window next to the new audio
+        let n_take = self
+            .old
+            .len()
+            .min((self.n_samples_keep + self.n_samples_len).saturating_sub(n_new));
+        let mut samples = Vec::with_capacity(n_take + n_new);
+        samples.extend_from_slice(&self.old[self.old.len() - n_take..]);
+        samples.extend(self.pending.drain(..n_new));
+        let start = self.old_end - n_take as u64;
+
+        self.n_iter += 1;
+        let commit =
+            (flush && self.pending.is_empty()) || self.n_iter.is_multiple_of(self.n_new_line);
+
+        self.old_end = start + samples.len() as u64;
+        self.old = if commit {
+            // keep part of the audio for the next window to mitigate word boundary issues
+            samples[samples.len().saturating_sub(self.n_samples_keep)..].to_vec()
+        } else {
+            samples.clone()
+        };
+
+        Some(Window {
+            samples,
+            start,
+            commit,
+        })
+    }
+}
+
+/// Transcribes audio that arrives in small chunks, such as from a microphone.
+///
+/// Audio is collected into a rolling window that is re-run through [WhisperState::full]
+/// every [StreamingConfig::step_ms] milliseconds. Results for the current window are reported
+/// as [StreamingEvent::Tentative] until the window reaches [StreamingConfig::length_ms],
+/// at which point they are reported as [StreamingEvent::Stable] and a new window is started.
+///
+/// # Examples
+/// ```no_run
+/// # use whisper_rs::{FullParams, SamplingStrategy, StreamingConfig, StreamingEvent, StreamingTranscriber, WhisperContext, WhisperContextParameters};
+/// # let ctx = WhisperContext::new_with_params("model.bin", WhisperContextParameters::default()).unwrap();
+/// # let microphone: Vec<Vec<f32>> = vec![];
+/// let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+/// params.set_single_segment(true);
+/// let mut transcriber =
+///     StreamingTranscriber::new(ctx.create_state().unwrap(), params, StreamingConfig::default());
+/// for chunk in microphone {
+///     for event in transcriber.push(&chunk).unwrap() {
+///         if let StreamingEvent::Stable(segments) = event {
+///             for segment in segments {
+///                 println!("[{} - {}]: {}", segment.t0, segment.t1, segment.text);
+///             }
+///         }
+///     }
+/// }
+/// ```
+pub struct StreamingTranscriber<'a, 'b> {
+    state: WhisperState,
+    params: FullParams<'a, 'b>,
+    config: StreamingConfig,
+    window: SlidingWindow,
+    prompt_tokens: Vec<c_int>,
+}
+
+impl<'a, 'b> StreamingTranscriber<'a, 'b> {
+    /// Create a new streaming transcriber.
+    ///
+    /// `params` are used for every run of the model, except that `no_context` is always set,
+    /// as the prompt is managed by the transcriber itself (see [StreamingConfig::use_prompt]).
+    /// Setting [FullParams::set_single_segment] is recommended, as in whisper.cpp's `stream` example.
+    pub fn new(state: WhisperState, params: FullParams<'a, 'b>, config: StreamingConfig) -> Self {
+        Self {
+            state,
+            params,
+            window: SlidingWindow::new(&config),
+            config,
+            prompt_tokens: Vec::new(),
+        }
+    }
+
+    /// Push PCM audio (32 bit floating point, 16 kHz, mono) into the transcriber.
+    ///
+    /// Runs the model once for every full step of audio that is now available,
+    /// and returns the resulting events in order. Returns no events if less than
+    /// a step of audio is pending.
+    pub fn push(&mut self, samples: &[f32]) -> Result<Vec<StreamingEvent>, WhisperError> {
+        self.window.push(samples);
+        self.drain(false)
+    }
+
+    /// Run the model on any pending audio and commit the results,
+    /// e.g. once the input stream has ended.
+    pub fn finish(&mut self) -> Result<Vec<StreamingEvent>, WhisperError> {
+        self.drain(true)
+    }
+
+    /// Access the underlying state, e.g. to inspect the results of the last run.
+    pub fn state(&self) -> &WhisperState {
+        &self.state
+    }
+
+    /// Consume the transcriber, returning the underlying state.
+    pub fn into_state(self) -> WhisperState {
+        self.state
+    }
+
+    fn drain(&mut self, flush: bool) -> Result<Vec<StreamingEvent>, WhisperError> {
+        let mut events = Vec::new();
+        while let Some(window) = self.window.next_window(flush) {
+            events.push(self.run(window)?);
+        }
+        Ok(events)
+    }
+
+    fn run(&mut self, window: Window) -> Result<StreamingEvent, WhisperError> {
+        let mut params: FullParams<'a, '_> = self.params.clone();
+        params.set_no_context(true);
+        if self.config.use_prompt {
+            params.set_tokens(&self.prompt_tokens);
+        }
+        self.state.full(params, &window.samples)?;
+
+        // window positions are in samples, timestamps in centiseconds
+        let offset = (window.start * 100 / WHISPER_SAMPLE_RATE as u64) as i64;
+        let mut segments = self
+            .state
+            .segments()
+            .map(|s| s.to_segment())
+            .collect::<Result<Vec<_>, _>>()?;
+        for segment in &mut segments {
+            segment.shift(offset);
+        }
+
+        if window.commit {
+            if self.config.use_prompt {
+                self.prompt_tokens = segments
+                    .iter()
+                    .flat_map(|s| s.tokens.iter().map(|t| t.id))
+                    .collect();
+            }
+            Ok(StreamingEvent::Stable(segments))
+        } else {
+            Ok(StreamingEvent::Tentative(segments))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(step_ms: u32, length_ms: u32, keep_ms: u32) -> StreamingConfig {
+        StreamingConfig {
+            step_ms,
+            length_ms,
+            keep_ms,
+            use_prompt: true,
+        }
+    }
+
+    #[test]
+    fn test_window_waits_for_full_step() {
+        let mut window = SlidingWindow::new(&config(1000, 3000, 0));
+        window.push(&[0.0; 15999]);
+        assert!(window.next_window(false).is_none());
+        window.push(&[0.0; 1]);
+        let w = window.next_window(false).unwrap();
+        assert_eq!(w.samples.len(), 16000);
+        assert_eq!(w.start, 0);
+        assert!(window.next_window(false).is_none());
+    }
+
+    #[test]
+    fn test_window_grows_then_commits() {
+        // n_new_line = 3000 / 1000 - 1 = 2
+        let mut window = SlidingWindow::new(&config(1000, 3000, 200));
+        window.push(&(0..16000 * 5).map(|x| x as f32).collect::<Vec<_>>());
+
+        let w = window.next_window(false).unwrap();
+        assert_eq!((w.start, w.samples.len(), w.commit), (0, 16000, false));
+
+        let w = window.next_window(false).unwrap();
+        assert_eq!((w.start, w.samples.len(), w.commit), (0, 32000, true));
+
+        // only the last 200 ms are kept after a commit
+        let w = window.next_window(false).unwrap();
+        assert_eq!((w.start, w.samples.len(), w.commit), (28800, 19200, false));
+        assert_eq!(w.samples[0], 28800.0);
+
+        let w = window.next_window(false).unwrap();
+        assert_eq!((w.start, w.samples.len(), w.commit), (28800, 35200, true));
+        assert_eq!(*w.samples.last().unwrap(), 63999.0);
+    }
+
+    #[test]
+    fn test_window_is_capped_at_length() {
+        // n_new_line = 1000 / 1000 - 1 = 0, clamped to 1: every window commits
+        let mut window = SlidingWindow::new(&config(1000, 1000, 100));
+        window.push(&[0.0; 16000 * 3]);
+        for i in 0..3 {
+            let w = window.next_window(false).unwrap();
+            assert!(w.commit);
+            assert!(w.samples.len() <= 16000 + 1600);
+            assert_eq!(w.start + w.samples.len() as u64, 16000 * (i + 1));
+        }
+    }
+
+    #[test]
+    fn test_window_flush() {
+        let mut window = SlidingWindow::new(&config(1000, 10000, 0));
+        window.push(&[0.0; 4000]);
+        assert!(window.next_window(false).is_none());
+        let w = window.next_window(true).unwrap();
+        assert_eq!((w.start, w.samples.len(), w.commit), (0, 4000, true));
+        assert!(window.next_window(true).is_none());
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "test-with-tiny-model")]
+mod test_with_tiny_model {
+    use super::*;
+    use crate::test_util::{load_sample, MODEL_PATH};
+    use crate::{SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+    // These tests expect that the tiny.en model has been downloaded
+    // using the script `sys/whisper.cpp/models/download-ggml-model.sh tiny.en`
+
+    #[test]
+    fn test_streaming_wav_in_chunks() {
+        let ctx = WhisperContext::new_with_params(MODEL_PATH, WhisperContextParameters::default())
+            .expect("Download the ggml-tiny.en model using 'sys/whisper.cpp/models/download-ggml-model.sh tiny.en'");
+        let samples = load_sample();
+
+        let mut params = FullParams::new(SamplingStrategy::default());
+        params.set_single_segment(true);
+        let config = StreamingConfig {
+            step_ms: 1000,
+            length_ms: 3000,
+            ..Default::default()
+        };
+        let mut transcriber =
+            StreamingTranscriber::new(ctx.create_state().unwrap(), params, config);
+
+        // feed 100 ms at a time
+        let mut events = Vec::new();
+        for chunk in samples.chunks(1600) {
+            events.extend(transcriber.push(chunk).unwrap());
+        }
+        events.extend(transcriber.finish().unwrap());
+
+        let stable = events
+            .iter()
+            .filter_map(|e| match e {
+                StreamingEvent::Stable(segments) => Some(segments),
+                StreamingEvent::Tentative(_) => None,
+            })
+            .flatten()
+            .collect::<Vec<_>>();
+        assert!(!stable.is_empty());
+        assert!(stable.windows(2).all(|w| w[0].t0 <= w[1].t0));
+
+        let duration_cs = samples.len() as i64 * 100 / WHISPER_SAMPLE_RATE as i64;
+        assert!(stable.iter().all(|s| s.t1 <= duration_cs + 100));
+    }
+}
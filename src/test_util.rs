@@ -0,0 +1,45 @@
+//! Fixtures shared by the tests of several modules.
+
+// which of them are used depends on the enabled features
+#![allow(dead_code)]
+
+use crate::{Segment, Transcript};
+
+/// Model used by the `test-with-tiny-model` tests.
+///
+/// Download it using the script `sys/whisper.cpp/models/download-ggml-model.sh tiny.en`.
+pub(crate) const MODEL_PATH: &str = "./sys/whisper.cpp/models/ggml-tiny.en.bin";
+
+/// Speech sample of the examples, 16 kHz mono 16-bit.
+pub(crate) const SAMPLE_PATH: &str = "./examples/full_usage/2830-3980-0043.wav";
+
+/// Load [SAMPLE_PATH] as samples ready for [crate::WhisperState::full].
+pub(crate) fn load_sample() -> Vec<f32> {
+    hound::WavReader::open(SAMPLE_PATH)
+        .unwrap()
+        .into_samples::<i16>()
+        .map(|x| x.unwrap() as f32 / 32768.0)
+        .collect()
+}
+
+/// A segment without tokens, with timestamps in centiseconds.
+pub(crate) fn segment(t0: i64, t1: i64, text: &str) -> Segment {
+    Segment {
+        t0,
+        t1,
+        text: text.to_string(),
+        speaker_turn_next: false,
+        #[cfg(feature = "no-speech-prob")]
+        no_speech_prob: 0.0,
+        tokens: Vec::new(),
+    }
+}
+
+/// An English transcript made of `segments`.
+pub(crate) fn transcript(segments: Vec<Segment>) -> Transcript {
+    Transcript {
+        lang_id: 0,
+        language: Some("en".to_string()),
+        segments,
+    }
+}
@@ -0,0 +1,457 @@
+//! Energy based voice activity detection, to skip silence before running the model.
+//!
+//! Long stretches of silence waste compute and are a common source of hallucinations.
+//! [EnergyVad] finds the spans of audio that contain speech,
+//! and [transcribe_speech] runs the model on only those spans,
+//! mapping the resulting timestamps back onto the original audio.
+//!
+//! # Examples
+//! ```no_run
+//! # use whisper_rs::vad::{transcribe_speech, EnergyVad, VadConfig};
+//! # use whisper_rs::{FullParams, SamplingStrategy, WhisperState};
+//! # fn run(state: &mut WhisperState, samples: &[f32]) -> Result<(), whisper_rs::WhisperError> {
+//! let spans = EnergyVad::new(VadConfig::default()).detect(samples);
+//! let params = FullParams::new(SamplingStrategy::default());
+//! let transcript = transcribe_speech(state, params, samples, &spans)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{FullParams, Transcript, WhisperError, WhisperState, WHISPER_SAMPLE_RATE};
+
+/// Configuration of [EnergyVad].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VadConfig {
+    /// Sample rate of the audio, in Hz.
+    ///
+    /// Defaults to [WHISPER_SAMPLE_RATE].
+    pub sample_rate: u32,
+    /// Length of the frames energy and zero crossings are measured over, in milliseconds.
+    ///
+    /// Defaults to 20.
+    pub frame_ms: u32,
+    /// Frames with an RMS level below this, in dBFS, are silence.
+    ///
+    /// Defaults to -40.
+    pub energy_threshold_db: f32,
+    /// Frames where more than this fraction of consecutive samples change sign are noise,
+    /// no matter how loud they are. Voiced speech stays well below 0.5, while white noise
+    /// sits right around it. Set to 1.0 to disable this check.
+    ///
+    /// Defaults to 0.4.
+    pub max_zero_crossing_rate: f32,
+    /// Speech shorter than this is dropped, in milliseconds.
+    ///
+    /// Defaults to 250.
+    pub min_speech_ms: u32,
+    /// Silence shorter than this does not split speech, in milliseconds.
+    ///
+    /// Defaults to 300.
+    pub min_silence_ms: u32,
+    /// Audio added before and after each span of speech, in milliseconds,
+    /// so quiet onsets and trailing consonants are not cut off.
+    ///
+    /// Defaults to 200.
+    pub padding_ms: u32,
+}
+
+impl Default for VadConfig {
+    fn default() -> Self {
+        Self {
+            sample_rate: WHISPER_SAMPLE_RATE,
+            frame_ms: 20,
+            energy_threshold_db: -40.0,
+            max_zero_crossing_rate: 0.4,
+            min_speech_ms: 250,
+            min_silence_ms: 300,
+            padding_ms: 200,
+        }
+    }
+}
+
+/// A span of audio containing speech, in samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SpeechSpan {
+    /// Index of the first sample of the span.
+    pub start: usize,
+    /// Index one past the last sample of the span.
+    pub end: usize,
+}
+
+impl SpeechSpan {
+    /// Number of samples in the span.
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Whether the span contains no samples.
+    pub fn is_empty(&self) -> bool {
+        self.end == self.start
+    }
+}
+
+/// Voice activity detector based on short-term energy and zero crossing rate.
+///
+/// This is cheap and needs no model, but cannot tell speech from other loud sounds
+/// such as music. Use it to trim silence, not to filter noise.
+#[derive(Debug, Clone)]
+pub struct EnergyVad {
+    config: VadConfig,
+}
+
+impl EnergyVad {
+    pub fn new(config: VadConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn config(&self) -> &VadConfig {
+        &self.config
+    }
+
+    /// Find the spans of `samples` that contain speech.
+    ///
+    /// The spans are sorted, do not overlap, and lie within `samples`.
+    pub fn detect(&self, samples: &[f32]) -> Vec<SpeechSpan> {
+        let frame_len = self.ms_to_samples(self.config.frame_ms).max(1);
+
+        // runs of speech frames, in samples
+        let mut spans = Vec::new();
+        let mut start = None;
+        for (i, frame) in samples.chunks(frame_len).enumerate() {
+            let pos = i * frame_len;
+            match (self.is_speech(frame), start) {
+                (true, None) => start = Some(pos),
+                (false, Some(s)) => {
+                    spans.push(SpeechSpan { start: s, end: pos });
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some(s) = start {
+            spans.push(SpeechSpan {
+                start: s,
+                end: samples.len(),
+            });
+        }
+
+        // bridge short pauses, then drop what is still too short to be speech
+        let spans = merge_closer_than(spans, self.ms_to_samples(self.config.min_silence_ms));
+        let min_speech = self.ms_to_samples(self.config.min_speech_ms);
+        let padding = self.ms_to_samples(self.config.padding_ms);
+        let spans = spans
+            .into_iter()
+            .filter(|s| s.len() >= min_speech)
+            .map(|s| SpeechSpan {
+                start: s.start.saturating_sub(padding),
+                end: (s.end + padding).min(samples.len()),
+            })
+            .collect();
+        merge_closer_than(spans, 0)
+    }
+
+    fn is_speech(&self, frame: &[f32]) -> bool {
+        let energy = frame.iter().map(|x| x * x).sum::<f32>() / frame.len() as f32;
+        // 10 * log10 of the mean square is 20 * log10 of the RMS
+        let level_db = 10.0 * energy.max(f32::MIN_POSITIVE).log10();
+        if level_db < self.config.energy_threshold_db {
+            return false;
+        }
+
+        let crossings = frame
+            .windows(2)
+            .filter(|w| (w[0] >= 0.0) != (w[1] >= 0.0))
+            .count();
+        let zero_crossing_rate = crossings as f32 / frame.len().saturating_sub(1).max(1) as f32;
+        zero_crossing_rate <= self.config.max_zero_crossing_rate
+    }
+
+    fn ms_to_samples(&self, ms: u32) -> usize {
+        (ms as u64 * self.config.sample_rate as u64 / 1000) as usize
+    }
+}
+
+impl Default for EnergyVad {
+    fn default() -> Self {
+        Self::new(VadConfig::default())
+    }
+}
+
+/// Merge sorted spans separated by `gap` samples or less.
+fn merge_closer_than(spans: Vec<SpeechSpan>, gap: usize) -> Vec<SpeechSpan> {
+    let mut merged: Vec<SpeechSpan> = Vec::with_capacity(spans.len());
+    for span in spans {
+        match merged.last_mut() {
+            Some(last) if span.start <= last.end + gap => last.end = last.end.max(span.end),
+            _ => merged.push(span),
+        }
+    }
+    merged
+}
+
+/// Silence inserted between spans passed to the model, in samples,
+/// so words at the end of one span do not run into the next.
+const SPAN_GAP: usize = WHISPER_SAMPLE_RATE as usize / 10;
+
+/// Maps positions in the audio passed to the model back to the original audio.
+#[derive(Debug)]
+struct TimeMap {
+    /// Start of each span in the model input, start of the same span in the original audio,
+    /// and its length, all in samples.
+    pieces: Vec<(usize, usize, usize)>,
+}
+
+impl TimeMap {
+    /// Concatenate the spans of `samples` separated by [SPAN_GAP] samples of silence.
+    fn concatenate(samples: &[f32], spans: &[SpeechSpan]) -> (Vec<f32>, Self) {
+        let mut audio = Vec::new();
+        let mut pieces = Vec::with_capacity(spans.len());
+        for span in spans {
+            let start = span.start.min(samples.len());
+            let end = span.end.clamp(start, samples.len());
+            if !audio.is_empty() {
+                audio.resize(audio.len() + SPAN_GAP, 0.0);
+            }
+            pieces.push((audio.len(), start, end - start));
+            audio.extend_from_slice(&samples[start..end]);
+        }
+        (audio, Self { pieces })
+    }
+
+    /// Map a timestamp in centiseconds in the model input to the original audio.
+    ///
+    /// Timestamps in the silence between spans are clamped to the end of the preceding span.
+    fn map(&self, t: i64) -> i64 {
+        let pos = t.max(0) as usize * SAMPLES_PER_CENTISECOND;
+        let i = self.pieces.partition_point(|&(start, _, _)| start <= pos);
+        let Some(&(start, original, len)) = self.pieces.get(i.saturating_sub(1)) else {
+            return t;
+        };
+        let original = original + pos.saturating_sub(start).min(len);
+        (original / SAMPLES_PER_CENTISECOND) as i64
+    }
+}
+
+const SAMPLES_PER_CENTISECOND: usize = WHISPER_SAMPLE_RATE as usize / 100;
+
+/// Transcribe only the spans of `samples` that contain speech.
+///
+/// The spans are concatenated with a short pause in between and transcribed with a single call to
+/// [WhisperState::full]. Segment and token timestamps in the result are mapped back to
+/// the original audio, so they line up with `samples` as if it had been transcribed whole.
+///
+/// # Arguments
+/// * `state` - The state to run the model on.
+/// * `params` - Parameters for the run.
+/// * `samples` - The full audio, as 16 kHz mono samples.
+/// * `spans` - Sorted, non-overlapping spans of `samples` to transcribe,
+///   usually from [EnergyVad::detect]. Spans are clamped to `samples`.
+///
+/// # Returns
+/// The transcript of the spans. If there are no spans, the model is not run at all,
+/// and the transcript is empty.
+pub fn transcribe_speech(
+    state: &mut WhisperState,
+    params: FullParams,
+    samples: &[f32],
+    spans: &[SpeechSpan],
+) -> Result<Transcript, WhisperError> {
+    let (audio, map) = TimeMap::concatenate(samples, spans);
+    if audio.is_empty() {
+        return Ok(Transcript {
+            lang_id: -1,
+            language: None,
+            segments: Vec::new(),
+        });
+    }
+
+    state.full(params, &audio)?;
+    let mut transcript = state.transcript()?;
+    for segment in &mut transcript.segments {
+        segment.t0 = map.map(segment.t0);
+        segment.t1 = map.map(segment.t1);
+        for token in &mut segment.tokens {
+            token.t0 = map.map(token.t0);
+            token.t1 = map.map(token.t1);
+            if token.t_dtw >= 0 {
+                token.t_dtw = map.map(token.t_dtw);
+            }
+        }
+    }
+    Ok(transcript)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    const RATE: usize = WHISPER_SAMPLE_RATE as usize;
+
+    fn tone(seconds: f32, amplitude: f32) -> Vec<f32> {
+        (0..(seconds * RATE as f32) as usize)
+            .map(|i| {
+                amplitude * (2.0 * std::f32::consts::PI * 220.0 * i as f32 / RATE as f32).sin()
+            })
+            .collect()
+    }
+
+    fn silence(seconds: f32) -> Vec<f32> {
+        vec![0.0; (seconds * RATE as f32) as usize]
+    }
+
+    /// Assert that `span` covers `start..end` seconds, give or take the padding and a frame.
+    fn assert_span(span: SpeechSpan, start: f32, end: f32) {
+        let tolerance = 0.25;
+        let (s, e) = (
+            span.start as f32 / RATE as f32,
+            span.end as f32 / RATE as f32,
+        );
+        assert!(
+            (s - start).abs() <= tolerance,
+            "span {:?} starts at {}",
+            span,
+            s
+        );
+        assert!(
+            (e - end).abs() <= tolerance,
+            "span {:?} ends at {}",
+            span,
+            e
+        );
+    }
+
+    #[test]
+    fn test_detects_tones_between_silence() {
+        let samples = [
+            silence(1.0),
+            tone(1.0, 0.5),
+            silence(2.0),
+            tone(0.5, 0.1),
+            silence(1.0),
+        ]
+        .concat();
+        let spans = EnergyVad::default().detect(&samples);
+        assert_eq!(spans.len(), 2, "{:?}", spans);
+        assert_span(spans[0], 1.0, 2.0);
+        assert_span(spans[1], 4.0, 4.5);
+    }
+
+    #[test]
+    fn test_short_pause_is_bridged() {
+        let samples = [tone(1.0, 0.5), silence(0.1), tone(1.0, 0.5)].concat();
+        let spans = EnergyVad::default().detect(&samples);
+        assert_eq!(
+            spans,
+            [SpeechSpan {
+                start: 0,
+                end: samples.len()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_quiet_and_short_sounds_are_dropped() {
+        let samples = [
+            silence(1.0),
+            tone(1.0, 0.001),
+            silence(1.0),
+            tone(0.1, 0.5),
+            silence(1.0),
+        ]
+        .concat();
+        assert!(EnergyVad::default().detect(&samples).is_empty());
+        assert!(EnergyVad::default().detect(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_noise_is_rejected_by_zero_crossings() {
+        let mut rng = rand::thread_rng();
+        let noise = (0..RATE)
+            .map(|_| rng.gen_range(-0.5..0.5))
+            .collect::<Vec<f32>>();
+        assert!(EnergyVad::default().detect(&noise).is_empty());
+
+        let vad = EnergyVad::new(VadConfig {
+            max_zero_crossing_rate: 1.0,
+            ..Default::default()
+        });
+        assert_eq!(vad.detect(&noise).len(), 1);
+    }
+
+    #[test]
+    fn test_time_map() {
+        let samples = (0..10 * RATE).map(|i| i as f32).collect::<Vec<_>>();
+        let spans = [
+            SpeechSpan {
+                start: RATE,
+                end: 2 * RATE,
+            },
+            SpeechSpan {
+                start: 5 * RATE,
+                end: 7 * RATE,
+            },
+        ];
+        let (audio, map) = TimeMap::concatenate(&samples, &spans);
+        assert_eq!(audio.len(), 3 * RATE + SPAN_GAP);
+        assert_eq!(audio[0], RATE as f32);
+        assert_eq!(audio[RATE + SPAN_GAP], (5 * RATE) as f32);
+
+        // start and inside of the first span
+        assert_eq!(map.map(0), 100);
+        assert_eq!(map.map(50), 150);
+        // inside the gap, clamped to the end of the first span
+        assert_eq!(map.map(105), 200);
+        // start and end of the second span
+        assert_eq!(map.map(110), 500);
+        assert_eq!(map.map(310), 700);
+        // past the end
+        assert_eq!(map.map(400), 700);
+    }
+
+    #[test]
+    fn test_time_map_clamps_spans() {
+        let samples = vec![0.0; RATE];
+        let spans = [SpeechSpan {
+            start: RATE / 2,
+            end: 2 * RATE,
+        }];
+        let (audio, map) = TimeMap::concatenate(&samples, &spans);
+        assert_eq!(audio.len(), RATE / 2);
+        assert_eq!(map.map(10), 60);
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "test-with-tiny-model")]
+mod test_with_tiny_model {
+    use super::*;
+    use crate::test_util::{load_sample, MODEL_PATH};
+    use crate::{SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+    // These tests expect that the tiny.en model has been downloaded
+    // using the script `sys/whisper.cpp/models/download-ggml-model.sh tiny.en`
+
+    #[test]
+    fn test_transcribe_speech_remaps_timestamps() {
+        let ctx = WhisperContext::new_with_params(MODEL_PATH, WhisperContextParameters::default())
+            .expect("Download the ggml-tiny.en model using 'sys/whisper.cpp/models/download-ggml-model.sh tiny.en'");
+        let mut state = ctx.create_state().unwrap();
+
+        // 20 seconds of silence before the speech
+        let lead = 20 * WHISPER_SAMPLE_RATE as usize;
+        let mut samples = vec![0.0; lead];
+        samples.extend(load_sample());
+        let spans = EnergyVad::default().detect(&samples);
+        assert!(!spans.is_empty());
+        assert!(spans[0].start >= lead - WHISPER_SAMPLE_RATE as usize);
+
+        let params = FullParams::new(SamplingStrategy::default());
+        let transcript = transcribe_speech(&mut state, params, &samples, &spans).unwrap();
+        assert!(!transcript.text().trim().is_empty());
+        let first = &transcript.segments[0];
+        assert!(first.t0 >= spans[0].start as i64 / 160);
+        let last = transcript.segments.last().unwrap();
+        assert!(last.t1 <= samples.len() as i64 / 160);
+    }
+}
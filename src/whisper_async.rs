@@ -0,0 +1,315 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::{ready, Context, Poll};
+use std::thread;
+
+use futures_core::Stream;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::{
+    CancellationHandle, FullParams, SegmentCallbackData, Transcript, WhisperError, WhisperState,
+};
+
+type ThreadResult<T> = thread::Result<Result<T, WhisperError>>;
+
+/// Async wrapper around a [WhisperState].
+///
+/// Inference runs on a dedicated OS thread, so awaiting it never blocks the executor.
+/// Only the `sync` primitives of tokio are used, so this works with any async runtime.
+///
+/// Dropping the future returned by [AsyncWhisperState::full] (or the stream returned by
+/// [AsyncWhisperState::full_stream]) cancels the run through [FullParams::set_cancellation].
+/// Any cancellation handle or abort callback already set on the params is replaced,
+/// while a deadline set with [FullParams::set_deadline] is kept.
+///
+/// Runs on the same state are serialized: a new run waits until the previous one,
+/// including a cancelled one, has released the state.
+#[derive(Debug, Clone)]
+pub struct AsyncWhisperState {
+    state: Arc<Mutex<WhisperState>>,
+}
+
+impl AsyncWhisperState {
+    pub fn new(state: WhisperState) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    /// Run the entire model and return the owned results.
+    ///
+    /// Async equivalent of [WhisperState::full] followed by [WhisperState::transcript].
+    ///
+    /// # Panics
+    /// Resumes the panic if the inference thread panicked.
+    pub async fn full(
+        &self,
+        mut params: FullParams<'static, 'static>,
+        audio: Vec<f32>,
+    ) -> Result<Transcript, WhisperError> {
+        let abort = AbortOnDrop::new(&mut params);
+        let result = self
+            .spawn(params, audio, |state| state.transcript())
+            .await
+            .expect("inference thread dropped its result sender");
+        abort.disarm();
+        result.unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    }
+
+    /// Run the entire model, yielding each segment as soon as whisper.cpp produces it.
+    ///
+    /// Segments come from the segment callback of [FullParams], which is replaced.
+    /// If the run fails, the error is yielded as the last item of the stream.
+    pub fn full_stream(
+        &self,
+        mut params: FullParams<'static, 'static>,
+        audio: Vec<f32>,
+    ) -> SegmentStream {
+        let abort = AbortOnDrop::new(&mut params);
+        let (tx, segments) = mpsc::unbounded_channel();
+        params.set_segment_callback_safe_lossy(move |segment| {
+            let _ = tx.send(segment);
+        });
+        let done = self.spawn(params, audio, |_| Ok(()));
+        SegmentStream {
+            segments,
+            done: Some(done),
+            result: None,
+            _abort: abort,
+        }
+    }
+
+    /// Get back the inner [WhisperState].
+    ///
+    /// Returns `Err(self)` if a run is still in progress or the state is shared with a clone.
+    pub fn into_inner(self) -> Result<WhisperState, Self> {
+        match Arc::try_unwrap(self.state) {
+            Ok(state) => Ok(state.into_inner().unwrap_or_else(PoisonError::into_inner)),
+            Err(state) => Err(Self { state }),
+        }
+    }
+
+    fn spawn<T, F>(
+        &self,
+        params: FullParams<'static, 'static>,
+        audio: Vec<f32>,
+        then: F,
+    ) -> oneshot::Receiver<ThreadResult<T>>
+    where
+        T: Send + 'static,
+        F: FnOnce(&WhisperState) -> Result<T, WhisperError> + Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        let state = Arc::clone(&self.state);
+        thread::spawn(move || {
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                // a panic can only happen between runs, the state itself is still usable
+                let mut state = state.lock().unwrap_or_else(PoisonError::into_inner);
+                state.full(params, &audio)?;
+                then(&state)
+            }));
+            let _ = tx.send(result);
+        });
+        rx
+    }
+}
+
+impl From<WhisperState> for AsyncWhisperState {
+    fn from(state: WhisperState) -> Self {
+        Self::new(state)
+    }
+}
+
+/// Stream of segments created by [AsyncWhisperState::full_stream].
+///
+/// Dropping the stream before it ends cancels the run.
+#[derive(Debug)]
+pub struct SegmentStream {
+    segments: mpsc::UnboundedReceiver<SegmentCallbackData>,
+    done: Option<oneshot::Receiver<ThreadResult<()>>>,
+    result: Option<ThreadResult<()>>,
+    _abort: AbortOnDrop,
+}
+
+impl Stream for SegmentStream {
+    type Item = Result<SegmentCallbackData, WhisperError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Poll::Ready(Some(segment)) = this.segments.poll_recv(cx) {
+                return Poll::Ready(Some(Ok(segment)));
+            }
+            match this.done.as_mut() {
+                // segments are sent before the result, so loop once more to drain them
+                Some(done) => {
+                    let result = ready!(Pin::new(done).poll(cx));
+                    this.done = None;
+                    this.result = Some(result.expect("inference thread dropped its result sender"));
+                }
+                None => {
+                    return Poll::Ready(match this.result.take() {
+                        Some(Ok(Err(e))) => Some(Err(e)),
+                        Some(Err(panic)) => std::panic::resume_unwind(panic),
+                        Some(Ok(Ok(()))) | None => None,
+                    })
+                }
+            }
+        }
+    }
+}
+
+/// Cancels a run when dropped, unless disarmed.
+#[derive(Debug)]
+struct AbortOnDrop(Option<CancellationHandle>);
+
+impl AbortOnDrop {
+    fn new(params: &mut FullParams) -> Self {
+        let handle = CancellationHandle::new();
+        params.set_cancellation(handle.clone());
+        Self(Some(handle))
+    }
+
+    fn disarm(mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        if let Some(handle) = self.0.take() {
+            handle.cancel();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(i: i32) -> SegmentCallbackData {
+        SegmentCallbackData {
+            segment: i,
+            start_timestamp: i as i64 * 100,
+            end_timestamp: (i as i64 + 1) * 100,
+            text: format!("segment {}", i),
+        }
+    }
+
+    fn stream() -> (
+        mpsc::UnboundedSender<SegmentCallbackData>,
+        oneshot::Sender<ThreadResult<()>>,
+        CancellationHandle,
+        SegmentStream,
+    ) {
+        let (tx, segments) = mpsc::unbounded_channel();
+        let (done_tx, done) = oneshot::channel();
+        let handle = CancellationHandle::new();
+        let stream = SegmentStream {
+            segments,
+            done: Some(done),
+            result: None,
+            _abort: AbortOnDrop(Some(handle.clone())),
+        };
+        (tx, done_tx, handle, stream)
+    }
+
+    async fn next(stream: &mut SegmentStream) -> Option<Result<SegmentCallbackData, WhisperError>> {
+        std::future::poll_fn(|cx| Pin::new(&mut *stream).poll_next(cx)).await
+    }
+
+    #[tokio::test]
+    async fn test_stream_drains_segments_before_ending() {
+        let (tx, done_tx, _, mut stream) = stream();
+        for i in 0..3 {
+            tx.send(segment(i)).unwrap();
+        }
+        done_tx.send(Ok(Ok(()))).unwrap();
+        // the sender can outlive the run, e.g. when the params were cloned
+        for i in 0..3 {
+            assert_eq!(next(&mut stream).await.unwrap().unwrap().segment, i);
+        }
+        assert!(next(&mut stream).await.is_none());
+        drop(tx);
+    }
+
+    #[tokio::test]
+    async fn test_stream_yields_error_last() {
+        let (tx, done_tx, _, mut stream) = stream();
+        tx.send(segment(0)).unwrap();
+        done_tx.send(Ok(Err(WhisperError::FailedToDecode))).unwrap();
+        assert!(next(&mut stream).await.unwrap().is_ok());
+        assert!(matches!(
+            next(&mut stream).await,
+            Some(Err(WhisperError::FailedToDecode))
+        ));
+        assert!(next(&mut stream).await.is_none());
+    }
+
+    #[test]
+    fn test_dropping_stream_aborts() {
+        let (_tx, _done_tx, handle, stream) = stream();
+        assert!(!handle.is_cancelled());
+        drop(stream);
+        assert!(handle.is_cancelled());
+    }
+
+    #[test]
+    fn test_disarmed_guard_does_not_abort() {
+        let handle = CancellationHandle::new();
+        AbortOnDrop(Some(handle.clone())).disarm();
+        assert!(!handle.is_cancelled());
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "test-with-tiny-model")]
+mod test_with_tiny_model {
+    use super::*;
+    use crate::test_util::{load_sample, MODEL_PATH};
+    use crate::{SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+    // These tests expect that the tiny.en model has been downloaded
+    // using the script `sys/whisper.cpp/models/download-ggml-model.sh tiny.en`
+
+    fn setup() -> (AsyncWhisperState, Vec<f32>) {
+        let ctx = WhisperContext::new_with_params(MODEL_PATH, WhisperContextParameters::default())
+            .expect("Download the ggml-tiny.en model using 'sys/whisper.cpp/models/download-ggml-model.sh tiny.en'");
+        let samples = load_sample();
+        (AsyncWhisperState::new(ctx.create_state().unwrap()), samples)
+    }
+
+    #[tokio::test]
+    async fn test_full_matches_stream() {
+        let (state, samples) = setup();
+        let params = FullParams::new(SamplingStrategy::default());
+
+        let transcript = state.full(params.clone(), samples.clone()).await.unwrap();
+        assert!(!transcript.segments.is_empty());
+
+        let mut stream = state.full_stream(params, samples);
+        let mut streamed = Vec::new();
+        while let Some(segment) =
+            std::future::poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await
+        {
+            streamed.push(segment.unwrap().text);
+        }
+        let expected = transcript
+            .segments
+            .iter()
+            .map(|s| s.text.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(streamed, expected);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_run_releases_state() {
+        let (state, samples) = setup();
+        let params = FullParams::new(SamplingStrategy::default());
+
+        drop(state.full_stream(params.clone(), samples.clone()));
+        let transcript = state.full(params, samples).await.unwrap();
+        assert!(!transcript.segments.is_empty());
+    }
+}
@@ -0,0 +1,144 @@
+use std::ffi::c_void;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// A closure owned by [crate::FullParams] and handed to whisper.cpp as callback user data.
+///
+/// Clones share the same closure, and calls through any of them are serialized by a mutex,
+/// so a cloned [crate::FullParams] can be used from several threads at once.
+/// The closure is dropped together with the last clone.
+pub(crate) struct OwnedCallback<F: ?Sized>(Arc<Mutex<Box<F>>>);
+
+impl<F: ?Sized> OwnedCallback<F> {
+    pub(crate) fn new(closure: Box<F>) -> Self {
+        Self(Arc::new(Mutex::new(closure)))
+    }
+
+    /// Pointer to pass as user data. Stays valid as long as any clone of this callback is alive.
+    pub(crate) fn user_data(&self) -> *mut c_void {
+        Arc::as_ptr(&self.0) as *mut c_void
+    }
+
+    /// Run `f` with the closure behind `user_data`.
+    ///
+    /// # Safety
+    /// `user_data` must come from [OwnedCallback::user_data] on a live callback of the same type.
+    pub(crate) unsafe fn call<R>(user_data: *mut c_void, f: impl FnOnce(&mut F) -> R) -> R {
+        let closure = &*(user_data as *const Mutex<Box<F>>);
+        // a panic in the closure does not leave it in an invalid state
+        let mut closure = closure.lock().unwrap_or_else(PoisonError::into_inner);
+        f(&mut **closure)
+    }
+}
+
+impl<F: ?Sized> Clone for OwnedCallback<F> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+// These tests do not call into whisper.cpp, so they can be run under Miri:
+// `cargo +nightly miri test --lib whisper_callback`
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    type Callback = dyn FnMut(usize) -> usize + Send;
+
+    struct DropCounter(Arc<AtomicUsize>);
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn counting_callback(drops: &Arc<AtomicUsize>) -> OwnedCallback<Callback> {
+        let guard = DropCounter(Arc::clone(drops));
+        let mut total = 0;
+        OwnedCallback::new(Box::new(move |n| {
+            let _ = &guard;
+            total += n;
+            total
+        }))
+    }
+
+    #[test]
+    fn test_call_through_user_data() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let callback = counting_callback(&drops);
+        let user_data = callback.user_data();
+        unsafe {
+            assert_eq!(OwnedCallback::<Callback>::call(user_data, |f| f(2)), 2);
+            assert_eq!(OwnedCallback::<Callback>::call(user_data, |f| f(3)), 5);
+        }
+    }
+
+    #[test]
+    fn test_user_data_is_stable_across_moves() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let callback = counting_callback(&drops);
+        let user_data = callback.user_data();
+        let moved = Box::new(callback);
+        assert_eq!(moved.user_data(), user_data);
+        unsafe {
+            assert_eq!(OwnedCallback::<Callback>::call(user_data, |f| f(1)), 1);
+        }
+    }
+
+    #[test]
+    fn test_clones_share_closure() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let callback = counting_callback(&drops);
+        let clone = callback.clone();
+        assert_eq!(clone.user_data(), callback.user_data());
+        unsafe {
+            OwnedCallback::<Callback>::call(callback.user_data(), |f| f(1));
+            assert_eq!(
+                OwnedCallback::<Callback>::call(clone.user_data(), |f| f(1)),
+                2
+            );
+        }
+    }
+
+    #[test]
+    fn test_dropped_with_last_clone() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let callback = counting_callback(&drops);
+        let clone = callback.clone();
+        drop(callback);
+        assert_eq!(drops.load(Ordering::SeqCst), 0);
+        unsafe {
+            assert_eq!(
+                OwnedCallback::<Callback>::call(clone.user_data(), |f| f(4)),
+                4
+            );
+        }
+        drop(clone);
+        assert_eq!(drops.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_calls_from_several_threads() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let callback = counting_callback(&drops);
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                let callback = callback.clone();
+                s.spawn(move || {
+                    for _ in 0..10 {
+                        unsafe { OwnedCallback::<Callback>::call(callback.user_data(), |f| f(1)) };
+                    }
+                });
+            }
+        });
+        unsafe {
+            assert_eq!(
+                OwnedCallback::<Callback>::call(callback.user_data(), |f| f(0)),
+                40
+            );
+        }
+        drop(callback);
+        assert_eq!(drops.load(Ordering::SeqCst), 1);
+    }
+}
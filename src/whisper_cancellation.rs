@@ -0,0 +1,171 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Handle to cancel an in-flight call to [crate::WhisperState::full] from another thread.
+///
+/// Attach it with [crate::FullParams::set_cancellation].
+/// All clones share the same flag, so cancelling any clone cancels every run using it.
+/// Once cancelled, a handle stays cancelled.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationHandle {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request cancellation of all runs using this handle.
+    ///
+    /// whisper.cpp checks for cancellation between compute steps,
+    /// so the run stops shortly after, not immediately.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether [CancellationHandle::cancel] has been called on this handle or any of its clones.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// Cancellation conditions attached to a [crate::FullParams].
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Cancellation {
+    pub(crate) handle: Option<CancellationHandle>,
+    pub(crate) deadline: Option<Instant>,
+    /// Set once the abort callback told whisper.cpp to stop.
+    triggered: Arc<AtomicBool>,
+}
+
+impl Cancellation {
+    pub(crate) fn new(handle: Option<CancellationHandle>, deadline: Option<Instant>) -> Self {
+        Self {
+            handle,
+            deadline,
+            triggered: Arc::default(),
+        }
+    }
+
+    /// Whether a run using these conditions must stop now.
+    pub(crate) fn is_aborted(&self) -> bool {
+        self.handle.as_ref().is_some_and(|h| h.is_cancelled())
+            || self.deadline.is_some_and(|d| Instant::now() >= d)
+    }
+
+    /// Like [Self::is_aborted], but remembers a positive answer, for use in the abort callback.
+    pub(crate) fn check(&self) -> bool {
+        let aborted = self.is_aborted();
+        if aborted {
+            self.triggered.store(true, Ordering::Relaxed);
+        }
+        aborted
+    }
+
+    /// Whether [Self::check] told whisper.cpp to stop,
+    /// as opposed to the conditions being met only after the run failed for another reason.
+    pub(crate) fn was_triggered(&self) -> bool {
+        self.triggered.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_clones_share_cancellation() {
+        let handle = CancellationHandle::new();
+        let clone = handle.clone();
+        assert!(!clone.is_cancelled());
+        std::thread::spawn(move || handle.cancel()).join().unwrap();
+        assert!(clone.is_cancelled());
+    }
+
+    #[test]
+    fn test_cancellation_conditions() {
+        assert!(!Cancellation::default().is_aborted());
+
+        let handle = CancellationHandle::new();
+        let cancellation = Cancellation::new(
+            Some(handle.clone()),
+            Some(Instant::now() + Duration::from_secs(3600)),
+        );
+        assert!(!cancellation.is_aborted());
+        handle.cancel();
+        assert!(cancellation.is_aborted());
+
+        let expired = Cancellation::new(None, Some(Instant::now()));
+        assert!(expired.is_aborted());
+    }
+
+    #[test]
+    fn test_check_records_abort() {
+        let handle = CancellationHandle::new();
+        let cancellation = Cancellation::new(Some(handle.clone()), None);
+        assert!(!cancellation.check());
+        handle.cancel();
+        // cancelled, but whisper.cpp was never told to stop
+        assert!(!cancellation.was_triggered());
+        assert!(cancellation.clone().check());
+        assert!(cancellation.was_triggered());
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "test-with-tiny-model")]
+mod test_with_tiny_model {
+    use super::*;
+    use crate::test_util::{load_sample, MODEL_PATH};
+    use crate::{
+        FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters, WhisperError,
+    };
+    use std::time::Duration;
+
+    // These tests expect that the tiny.en model has been downloaded
+    // using the script `sys/whisper.cpp/models/download-ggml-model.sh tiny.en`
+
+    fn load_long_sample() -> Vec<f32> {
+        // long enough that the run is still going when it gets cancelled
+        load_sample().repeat(10)
+    }
+
+    #[test]
+    fn test_cancel_from_other_thread() {
+        let ctx = WhisperContext::new_with_params(MODEL_PATH, WhisperContextParameters::default())
+            .expect("Download the ggml-tiny.en model using 'sys/whisper.cpp/models/download-ggml-model.sh tiny.en'");
+        let mut state = ctx.create_state().unwrap();
+        let samples = load_long_sample();
+
+        let handle = CancellationHandle::new();
+        let mut params = FullParams::new(SamplingStrategy::default());
+        params.set_cancellation(handle.clone());
+        let canceller = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            handle.cancel();
+        });
+        assert!(matches!(
+            state.full(params, &samples),
+            Err(WhisperError::Aborted)
+        ));
+        canceller.join().unwrap();
+    }
+
+    #[test]
+    fn test_deadline() {
+        let ctx = WhisperContext::new_with_params(MODEL_PATH, WhisperContextParameters::default())
+            .expect("Download the ggml-tiny.en model using 'sys/whisper.cpp/models/download-ggml-model.sh tiny.en'");
+        let mut state = ctx.create_state().unwrap();
+        let samples = load_long_sample();
+
+        let mut params = FullParams::new(SamplingStrategy::default());
+        params.set_deadline(Instant::now() + Duration::from_millis(50));
+        assert!(matches!(
+            state.full(params, &samples),
+            Err(WhisperError::Aborted)
+        ));
+    }
+}
@@ -1,6 +1,8 @@
 use crate::error::WhisperError;
+use crate::whisper_model_loader::ModelLoader;
 use crate::WhisperToken;
 use std::ffi::{c_int, CStr, CString};
+use std::io::Read;
 
 /// Safe Rust wrapper around a Whisper context.
 ///
@@ -70,6 +72,30 @@ impl WhisperInnerContext {
         }
     }
 
+    /// Create a new WhisperContext from a reader.
+    ///
+    /// # Arguments
+    /// * reader: The reader to load the model from.
+    /// * parameters: A parameter struct containing the parameters to use.
+    ///
+    /// # Returns
+    /// Ok(Self) on success, Err(WhisperError) on failure.
+    ///
+    /// # C++ equivalent
+    /// `struct whisper_context * whisper_init_with_params_no_state(struct whisper_model_loader * loader, struct whisper_context_params params);`
+    pub fn new_from_reader_with_params<R: Read>(
+        reader: R,
+        parameters: WhisperContextParameters,
+    ) -> Result<Self, WhisperError> {
+        let ctx = ModelLoader::new(reader).load(
+            |loader| unsafe {
+                whisper_rs_sys::whisper_init_with_params_no_state(loader, parameters.to_c_struct())
+            },
+            whisper_rs_sys::whisper_free,
+        )?;
+        Ok(Self { ctx })
+    }
+
     /// Convert the provided text into tokens.
     ///
     /// # Arguments
@@ -640,7 +666,7 @@ pub enum DtwMode<'a> {
     ModelPreset { model_preset: DtwModelPreset },
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DtwModelPreset {
     TinyEn,
     Tiny,
@@ -660,7 +686,7 @@ pub enum DtwModelPreset {
 #[cfg(feature = "test-with-tiny-model")]
 mod test_with_tiny_model {
     use super::*;
-    const MODEL_PATH: &str = "./sys/whisper.cpp/models/ggml-tiny.en.bin";
+    use crate::test_util::MODEL_PATH;
 
     // These tests expect that the tiny.en model has been downloaded
     // using the script `sys/whisper.cpp/models/download-ggml-model.sh tiny.en`
@@ -677,4 +703,33 @@ mod test_with_tiny_model {
             .join("");
         assert_eq!(text_in, text_out);
     }
+
+    #[test]
+    fn test_load_from_reader() {
+        let file = std::fs::File::open(MODEL_PATH).expect("Download the ggml-tiny.en model using 'sys/whisper.cpp/models/download-ggml-model.sh tiny.en'");
+        let ctx = WhisperInnerContext::new_from_reader_with_params(
+            std::io::BufReader::new(file),
+            WhisperContextParameters::default(),
+        )
+        .unwrap();
+        let from_path =
+            WhisperInnerContext::new_with_params(MODEL_PATH, WhisperContextParameters::default())
+                .unwrap();
+        assert_eq!(ctx.n_vocab(), from_path.n_vocab());
+        assert_eq!(
+            ctx.model_type_readable().unwrap(),
+            from_path.model_type_readable().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_load_from_truncated_reader() {
+        let buffer = std::fs::read(MODEL_PATH).unwrap();
+        let truncated = &buffer[..buffer.len() / 2];
+        assert!(WhisperInnerContext::new_from_reader_with_params(
+            truncated,
+            WhisperContextParameters::default()
+        )
+        .is_err());
+    }
 }
@@ -1,8 +1,10 @@
 use std::ffi::{c_int, CStr};
+use std::io::Read;
 use std::sync::Arc;
 
 use crate::{
-    WhisperContextParameters, WhisperError, WhisperInnerContext, WhisperState, WhisperToken,
+    FullParams, Transcript, WhisperContextParameters, WhisperError, WhisperInnerContext,
+    WhisperState, WhisperToken,
 };
 
 pub struct WhisperContext {
@@ -51,6 +53,38 @@ impl WhisperContext {
         Ok(Self::wrap(ctx))
     }
 
+    /// Create a new WhisperContext from any reader, such as a decompressing or decrypting one.
+    ///
+    /// The model is read front to back once, straight into the model's own buffers,
+    /// so unlike [Self::new_from_buffer_with_params] it never has to be held in memory twice.
+    ///
+    /// There is deliberately no constructor memory-mapping the model file:
+    /// whisper.cpp copies every tensor out of whatever it loads from into its own buffers,
+    /// so a mapping would neither save memory nor let processes share the pages of the weights.
+    /// Use this or [Self::new_with_params] to avoid reading a large model into memory first.
+    ///
+    /// # Arguments
+    /// * reader: The reader to load the model from. Wrap it in a [std::io::BufReader]
+    ///   if small reads are expensive, as most reads are only a few bytes long.
+    /// * parameters: A parameter struct containing the parameters to use.
+    ///
+    /// # Returns
+    /// Ok(Self) on success, Err(WhisperError) on failure.
+    /// Err(WhisperError::FailedToReadModel) if the reader returned an error.
+    ///
+    /// # Panics
+    /// If the reader panics, the panic is resumed once whisper.cpp gave up on the model.
+    ///
+    /// # C++ equivalent
+    /// `struct whisper_context * whisper_init_with_params_no_state(struct whisper_model_loader * loader, struct whisper_context_params params);`
+    pub fn new_from_reader<R: Read>(
+        reader: R,
+        parameters: WhisperContextParameters,
+    ) -> Result<Self, WhisperError> {
+        let ctx = WhisperInnerContext::new_from_reader_with_params(reader, parameters)?;
+        Ok(Self::wrap(ctx))
+    }
+
     /// Convert the provided text into tokens.
     ///
     /// # Arguments
@@ -373,6 +407,10 @@ impl WhisperContext {
 
     /// Print performance statistics to stderr.
     ///
+    /// This only includes the time to load the model, as whisper.cpp prints the time of each step
+    /// only for the default state, which contexts created by whisper-rs do not have.
+    /// Use [WhisperState::last_run_timings] for the time of a run.
+    ///
     /// # C++ equivalent
     /// `void whisper_print_timings(struct whisper_context * ctx)`
     #[inline]
@@ -406,8 +444,6 @@ impl WhisperContext {
         self.ctx.token_transcribe()
     }
 
-    // we don't implement `whisper_init()` here since i have zero clue what `whisper_model_loader` does
-
     /// Create a new state object, ready for use.
     ///
     /// # Returns
@@ -424,4 +460,46 @@ impl WhisperContext {
             Ok(WhisperState::new(self.ctx.clone(), state))
         }
     }
+
+    /// Split the audio into `n_processors` parts and transcribe them in parallel,
+    /// each on its own state.
+    ///
+    /// This does the same as whisper.cpp's `whisper_full_parallel`,
+    /// which cannot be called directly as it needs the default state
+    /// that whisper-rs contexts are created without.
+    /// The results are combined into a [Transcript] with timestamps relative to
+    /// the start of `data`, as the parts do not share a state to read them back from.
+    ///
+    /// The audio is cut without regard for speech, so transcription around the cuts
+    /// may be less accurate than with [WhisperState::full].
+    /// For long recordings, consider [crate::ChunkedTranscriber], which cuts at silence.
+    ///
+    /// # Callbacks and threads
+    /// * Every part runs with [FullParams::set_n_threads] threads,
+    ///   so up to `n_processors * n_threads` threads are busy at once.
+    /// * Like in whisper.cpp, the new segment and progress callbacks only run for the first part,
+    ///   with timestamps relative to it.
+    /// * All other callbacks run for every part, possibly from several threads at once.
+    ///   Calls to safe callbacks are serialized.
+    ///
+    /// # Arguments
+    /// * params: [crate::FullParams] struct.
+    /// * data: Raw PCM audio data, 32 bit floating point at a sample rate of 16 kHz, 1 channel.
+    /// * n_processors: Number of parts to split the audio into.
+    ///   Reduced so that every part is at least a second long.
+    ///
+    /// # Returns
+    /// Ok(Transcript) on success, Err(WhisperError) on failure.
+    /// If several parts fail, the error of the earliest one is returned.
+    ///
+    /// # C++ equivalent
+    /// `int whisper_full_parallel(struct whisper_context * ctx, struct whisper_full_params params, const float * samples, int n_samples, int n_processors)`
+    pub fn full_parallel(
+        &self,
+        params: &FullParams,
+        data: &[f32],
+        n_processors: c_int,
+    ) -> Result<Transcript, WhisperError> {
+        crate::chunked::full_parallel(self, params, data, n_processors)
+    }
 }
@@ -0,0 +1,162 @@
+use std::ffi::{c_int, CStr};
+use std::marker::PhantomData;
+
+use crate::{WhisperError, WhisperToken, WhisperTokenData};
+
+/// Context passed to the closure set with [crate::FullParams::set_logits_filter_safe].
+///
+/// Only valid for the duration of a single callback invocation.
+#[derive(Debug, Clone, Copy)]
+pub struct LogitsFilterContext<'a> {
+    ctx: *mut whisper_rs_sys::whisper_context,
+    tokens: &'a [WhisperTokenData],
+    _marker: PhantomData<&'a whisper_rs_sys::whisper_context>,
+}
+
+impl<'a> LogitsFilterContext<'a> {
+    /// # Safety
+    /// `ctx` must be a valid context pointer for the lifetime `'a`.
+    pub(crate) unsafe fn new(
+        ctx: *mut whisper_rs_sys::whisper_context,
+        tokens: &'a [WhisperTokenData],
+    ) -> Self {
+        Self {
+            ctx,
+            tokens,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Tokens decoded so far by the decoder the logits belong to.
+    #[inline]
+    pub fn tokens(&self) -> &'a [WhisperTokenData] {
+        self.tokens
+    }
+
+    /// Number of tokens in the vocabulary, which is also the length of the logits slice.
+    ///
+    /// # C++ equivalent
+    /// `int whisper_n_vocab(struct whisper_context * ctx)`
+    #[inline]
+    pub fn n_vocab(&self) -> c_int {
+        unsafe { whisper_rs_sys::whisper_n_vocab(self.ctx) }
+    }
+
+    /// Convert a token ID to a string.
+    ///
+    /// # Returns
+    /// Ok(&str) on success, Err(WhisperError) on failure.
+    ///
+    /// # C++ equivalent
+    /// `const char * whisper_token_to_str(struct whisper_context * ctx, whisper_token token)`
+    pub fn token_to_str(&self, token_id: WhisperToken) -> Result<&'a str, WhisperError> {
+        Ok(self.token_to_cstr(token_id)?.to_str()?)
+    }
+
+    /// Convert a token ID to a &CStr.
+    ///
+    /// # Returns
+    /// Ok(&CStr) on success, Err(WhisperError) on failure.
+    ///
+    /// # C++ equivalent
+    /// `const char * whisper_token_to_str(struct whisper_context * ctx, whisper_token token)`
+    pub fn token_to_cstr(&self, token_id: WhisperToken) -> Result<&'a CStr, WhisperError> {
+        let ret = unsafe { whisper_rs_sys::whisper_token_to_str(self.ctx, token_id) };
+        if ret.is_null() {
+            return Err(WhisperError::NullPointer);
+        }
+        Ok(unsafe { CStr::from_ptr(ret) })
+    }
+
+    /// Get the ID of the eot token.
+    ///
+    /// # C++ equivalent
+    /// `whisper_token whisper_token_eot (struct whisper_context * ctx)`
+    #[inline]
+    pub fn token_eot(&self) -> WhisperToken {
+        unsafe { whisper_rs_sys::whisper_token_eot(self.ctx) }
+    }
+
+    /// Get the ID of the sot token.
+    ///
+    /// # C++ equivalent
+    /// `whisper_token whisper_token_sot (struct whisper_context * ctx)`
+    #[inline]
+    pub fn token_sot(&self) -> WhisperToken {
+        unsafe { whisper_rs_sys::whisper_token_sot(self.ctx) }
+    }
+
+    /// Get the ID of the not token.
+    ///
+    /// # C++ equivalent
+    /// `whisper_token whisper_token_not (struct whisper_context * ctx)`
+    #[inline]
+    pub fn token_not(&self) -> WhisperToken {
+        unsafe { whisper_rs_sys::whisper_token_not(self.ctx) }
+    }
+
+    /// Get the ID of the beg token, the first timestamp token.
+    /// All token IDs at or above this one are timestamps.
+    ///
+    /// # C++ equivalent
+    /// `whisper_token whisper_token_beg (struct whisper_context * ctx)`
+    #[inline]
+    pub fn token_beg(&self) -> WhisperToken {
+        unsafe { whisper_rs_sys::whisper_token_beg(self.ctx) }
+    }
+
+    /// Get the ID of a specified language token
+    ///
+    /// # C++ equivalent
+    /// `whisper_token whisper_token_lang(struct whisper_context * ctx, int lang_id)`
+    #[inline]
+    pub fn token_lang(&self, lang_id: c_int) -> WhisperToken {
+        unsafe { whisper_rs_sys::whisper_token_lang(self.ctx, lang_id) }
+    }
+
+    /// Whether a token is a special (non-text) token, i.e. eot or above.
+    #[inline]
+    pub fn is_special(&self, token_id: WhisperToken) -> bool {
+        token_id >= self.token_eot()
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "test-with-tiny-model")]
+mod test_with_tiny_model {
+    use crate::test_util::{load_sample, MODEL_PATH};
+    use crate::{
+        FullParams, LogitsFilterContext, SamplingStrategy, WhisperContext, WhisperContextParameters,
+    };
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    // These tests expect that the tiny.en model has been downloaded
+    // using the script `sys/whisper.cpp/models/download-ggml-model.sh tiny.en`
+
+    #[test]
+    fn test_logits_filter_suppresses_tokens() {
+        let ctx = WhisperContext::new_with_params(MODEL_PATH, WhisperContextParameters::default())
+            .expect("Download the ggml-tiny.en model using 'sys/whisper.cpp/models/download-ggml-model.sh tiny.en'");
+        let mut state = ctx.create_state().unwrap();
+        let samples = load_sample();
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let calls_in_filter = Arc::clone(&calls);
+        let mut params = FullParams::new(SamplingStrategy::default());
+        // only allow the decoder to end the segment or emit timestamps
+        params.set_logits_filter_safe(move |ctx: LogitsFilterContext, logits: &mut [f32]| {
+            calls_in_filter.fetch_add(1, Ordering::Relaxed);
+            assert_eq!(logits.len(), ctx.n_vocab() as usize);
+            for (id, logit) in logits.iter_mut().enumerate() {
+                if !ctx.is_special(id as _) {
+                    *logit = f32::NEG_INFINITY;
+                }
+            }
+        });
+        state.full(params, &samples).unwrap();
+
+        assert!(calls.load(Ordering::Relaxed) > 0);
+        assert!(state.transcript().unwrap().text().trim().is_empty());
+    }
+}
@@ -0,0 +1,233 @@
+//! Feeding models to whisper.cpp from any [Read] implementation through `whisper_model_loader`.
+//!
+//! whisper.cpp pulls the model through three callbacks:
+//! `read` copies the next bytes straight into their final destination,
+//! such as the tensor buffers, `eof` tells whether the end of the model was reached,
+//! and `close` is called once loading is done.
+//! None of them can report an error, so read errors are recorded here and surfaced afterwards.
+
+use std::any::Any;
+use std::ffi::c_void;
+use std::io::{ErrorKind, Read};
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::common_logging::generic_error;
+use crate::WhisperError;
+
+pub(crate) struct ModelLoader<R> {
+    reader: R,
+    eof: bool,
+    read_failed: bool,
+    panic: Option<Box<dyn Any + Send>>,
+}
+
+impl<R: Read> ModelLoader<R> {
+    pub(crate) fn new(reader: R) -> Self {
+        Self {
+            reader,
+            eof: false,
+            read_failed: false,
+            panic: None,
+        }
+    }
+
+    /// Call `init` with a `whisper_model_loader` reading from this loader.
+    ///
+    /// Returns the pointer created by `init`, or an error if it is null or reading the model failed.
+    /// A panic in the reader is resumed once `init` returns.
+    /// In both cases, a pointer created regardless is released with `free`.
+    pub(crate) fn load<T>(
+        mut self,
+        init: impl FnOnce(*mut whisper_rs_sys::whisper_model_loader) -> *mut T,
+        free: unsafe extern "C" fn(*mut T),
+    ) -> Result<*mut T, WhisperError> {
+        let mut loader = whisper_rs_sys::whisper_model_loader {
+            context: &mut self as *mut Self as *mut c_void,
+            read: Some(Self::read),
+            eof: Some(Self::eof),
+            close: Some(Self::close),
+        };
+        // the loader is only used during this call
+        let ptr = init(&mut loader);
+
+        if (self.panic.is_some() || self.read_failed) && !ptr.is_null() {
+            // the input ended right after the last tensor, but the model is not trustworthy
+            unsafe { free(ptr) };
+        }
+        if let Some(payload) = self.panic {
+            panic::resume_unwind(payload);
+        }
+        if self.read_failed {
+            return Err(WhisperError::FailedToReadModel);
+        }
+        if ptr.is_null() {
+            Err(WhisperError::InitError)
+        } else {
+            Ok(ptr)
+        }
+    }
+
+    /// Fill `buf` from the reader, stopping early only at the end of the input or on an error.
+    fn fill(&mut self, buf: &mut [u8]) -> usize {
+        let mut filled = 0;
+        while filled < buf.len() && !self.eof {
+            match self.reader.read(&mut buf[filled..]) {
+                Ok(0) => self.eof = true,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                // only logged if a logging backend is enabled
+                #[allow(unused_variables)]
+                Err(e) => {
+                    generic_error!("failed to read model: {}", e);
+                    self.read_failed = true;
+                    self.eof = true;
+                }
+            }
+        }
+        filled
+    }
+
+    unsafe extern "C" fn read(ctx: *mut c_void, output: *mut c_void, read_size: usize) -> usize {
+        let this = &mut *(ctx as *mut Self);
+        let buf = std::slice::from_raw_parts_mut(output as *mut u8, read_size);
+        let filled = if this.panic.is_some() {
+            0
+        } else {
+            match panic::catch_unwind(AssertUnwindSafe(|| this.fill(buf))) {
+                Ok(filled) => filled,
+                Err(payload) => {
+                    this.panic = Some(payload);
+                    this.eof = true;
+                    0
+                }
+            }
+        };
+        // whisper.cpp does not check how much was read,
+        // so never leave it with uninitialized or stale data
+        buf[filled..].fill(0);
+        filled
+    }
+
+    unsafe extern "C" fn eof(ctx: *mut c_void) -> bool {
+        let this = &*(ctx as *const Self);
+        this.eof
+    }
+
+    unsafe extern "C" fn close(_ctx: *mut c_void) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ptr;
+
+    struct Chunks<'a> {
+        data: &'a [u8],
+        chunk: usize,
+        fail_at: Option<usize>,
+        read: usize,
+    }
+
+    impl Read for Chunks<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if self.fail_at.is_some_and(|at| self.read >= at) {
+                return Err(std::io::Error::other("broken archive"));
+            }
+            let n = buf.len().min(self.chunk).min(self.data.len());
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            self.read += n;
+            Ok(n)
+        }
+    }
+
+    unsafe extern "C" fn free_nothing(_ptr: *mut u8) {}
+
+    /// Read chunks of `sizes` bytes the way whisper.cpp does, until the loader reports the end.
+    /// Returns the result of loading along with how much each read returned and what it wrote.
+    #[allow(clippy::type_complexity)]
+    fn read_all<R: Read>(
+        reader: R,
+        sizes: &[usize],
+    ) -> (Result<(), WhisperError>, Vec<(usize, Vec<u8>)>) {
+        let mut reads = Vec::new();
+        let result = ModelLoader::new(reader)
+            .load(
+                |loader| unsafe {
+                    let loader = &mut *loader;
+                    for &size in sizes {
+                        let mut out = vec![0xff; size];
+                        let n = loader.read.unwrap()(loader.context, out.as_mut_ptr() as _, size);
+                        reads.push((n, out));
+                        if loader.eof.unwrap()(loader.context) {
+                            break;
+                        }
+                    }
+                    loader.close.unwrap()(loader.context);
+                    ptr::NonNull::<u8>::dangling().as_ptr()
+                },
+                free_nothing,
+            )
+            .map(|_| ());
+        (result, reads)
+    }
+
+    #[test]
+    fn test_reads_across_chunks() {
+        let data = [1, 2, 3, 4, 5, 6, 7];
+        let reader = Chunks {
+            data: &data,
+            chunk: 2,
+            fail_at: None,
+            read: 0,
+        };
+        let (result, reads) = read_all(reader, &[3, 3, 3, 3]);
+        assert!(result.is_ok());
+        // the short read at the end is zero filled and ends the loading
+        assert_eq!(
+            reads,
+            [(3, vec![1, 2, 3]), (3, vec![4, 5, 6]), (1, vec![7, 0, 0])]
+        );
+    }
+
+    #[test]
+    fn test_slice_reader() {
+        let data: &[u8] = &[1, 2, 3, 4];
+        let (result, reads) = read_all(data, &[4, 4]);
+        assert!(result.is_ok());
+        assert_eq!(reads, [(4, vec![1, 2, 3, 4]), (0, vec![0, 0, 0, 0])]);
+    }
+
+    #[test]
+    fn test_read_error() {
+        let data = [1; 10];
+        let reader = Chunks {
+            data: &data,
+            chunk: 4,
+            fail_at: Some(4),
+            read: 0,
+        };
+        let (result, reads) = read_all(reader, &[6, 6]);
+        assert!(matches!(result, Err(WhisperError::FailedToReadModel)));
+        assert_eq!(reads, [(4, vec![1, 1, 1, 1, 0, 0])]);
+    }
+
+    #[test]
+    fn test_null_pointer_is_init_error() {
+        let data: &[u8] = &[];
+        let result = ModelLoader::new(data).load(|_| ptr::null_mut::<u8>(), free_nothing);
+        assert!(matches!(result, Err(WhisperError::InitError)));
+    }
+
+    #[test]
+    #[should_panic(expected = "reader panicked")]
+    fn test_panic_is_resumed() {
+        struct Panics;
+        impl Read for Panics {
+            fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+                panic!("reader panicked")
+            }
+        }
+        let _ = read_all(Panics, &[4]);
+    }
+}
@@ -1,8 +1,13 @@
+use crate::whisper_callback::OwnedCallback;
+use crate::whisper_cancellation::Cancellation;
 use crate::whisper_grammar::WhisperGrammarElement;
+use crate::CancellationHandle;
 use std::ffi::{c_char, c_float, c_int, CString};
 use std::marker::PhantomData;
-use std::sync::Arc;
+use std::time::Instant;
 use whisper_rs_sys::whisper_token;
+#[cfg(feature = "whisper-vad")]
+use {std::ffi::CStr, std::sync::Arc};
 
 #[derive(Debug, Clone)]
 pub enum SamplingStrategy {
@@ -30,7 +35,11 @@ pub struct SegmentCallbackData {
     pub text: String,
 }
 
-type SegmentCallbackFn = Box<dyn FnMut(SegmentCallbackData)>;
+type SegmentCallbackFn = dyn FnMut(SegmentCallbackData) + Send;
+type ProgressCallbackFn = dyn FnMut(i32) + Send;
+type AbortCallbackFn = dyn FnMut() -> bool + Send;
+type EncoderBeginCallbackFn = dyn FnMut() -> bool + Send;
+type LogitsFilterFn = dyn FnMut(crate::LogitsFilterContext, &mut [f32]) + Send;
 
 #[derive(Clone)]
 pub struct FullParams<'a, 'b> {
@@ -38,9 +47,14 @@ pub struct FullParams<'a, 'b> {
     phantom_lang: PhantomData<&'a str>,
     phantom_tokens: PhantomData<&'b [c_int]>,
     grammar: Option<Vec<whisper_rs_sys::whisper_grammar_element>>,
-    progress_callback_safe: Option<Arc<Box<dyn FnMut(i32)>>>,
-    abort_callback_safe: Option<Arc<Box<dyn FnMut() -> bool>>>,
-    segment_calllback_safe: Option<Arc<SegmentCallbackFn>>,
+    #[cfg(feature = "whisper-vad")]
+    vad_model_path: Option<Arc<CStr>>,
+    progress_callback_safe: Option<OwnedCallback<ProgressCallbackFn>>,
+    abort_callback_safe: Option<OwnedCallback<AbortCallbackFn>>,
+    segment_callback_safe: Option<OwnedCallback<SegmentCallbackFn>>,
+    encoder_begin_callback_safe: Option<OwnedCallback<EncoderBeginCallbackFn>>,
+    logits_filter_callback_safe: Option<OwnedCallback<LogitsFilterFn>>,
+    pub(crate) cancellation: Cancellation,
 }
 
 impl<'a, 'b> FullParams<'a, 'b> {
@@ -75,9 +89,14 @@ impl<'a, 'b> FullParams<'a, 'b> {
             phantom_lang: PhantomData,
             phantom_tokens: PhantomData,
             grammar: None,
+            #[cfg(feature = "whisper-vad")]
+            vad_model_path: None,
             progress_callback_safe: None,
             abort_callback_safe: None,
-            segment_calllback_safe: None,
+            segment_callback_safe: None,
+            encoder_begin_callback_safe: None,
+            logits_filter_callback_safe: None,
+            cancellation: Cancellation::default(),
         }
     }
 
@@ -406,63 +425,51 @@ impl<'a, 'b> FullParams<'a, 'b> {
     /// Defaults to None.
     pub fn set_segment_callback_safe<O, F>(&mut self, closure: O)
     where
-        F: FnMut(SegmentCallbackData) + 'static,
+        F: FnMut(SegmentCallbackData) + Send + 'static,
         O: Into<Option<F>>,
     {
         use std::ffi::{c_void, CStr};
         use whisper_rs_sys::{whisper_context, whisper_state};
 
-        extern "C" fn trampoline<F>(
+        unsafe extern "C" fn trampoline(
             _: *mut whisper_context,
             state: *mut whisper_state,
             n_new: i32,
             user_data: *mut c_void,
-        ) where
-            F: FnMut(SegmentCallbackData) + 'static,
-        {
-            unsafe {
-                let user_data = &mut *(user_data as *mut SegmentCallbackFn);
-                let n_segments = whisper_rs_sys::whisper_full_n_segments_from_state(state);
-                let s0 = n_segments - n_new;
-                //let user_data = user_data as *mut Box<dyn FnMut(SegmentCallbackData)>;
-
-                for i in s0..n_segments {
-                    let text = whisper_rs_sys::whisper_full_get_segment_text_from_state(state, i);
-                    let text = CStr::from_ptr(text);
-
-                    let t0 = whisper_rs_sys::whisper_full_get_segment_t0_from_state(state, i);
-                    let t1 = whisper_rs_sys::whisper_full_get_segment_t1_from_state(state, i);
-
-                    match text.to_str() {
-                        Ok(n) => user_data(SegmentCallbackData {
+        ) {
+            let n_segments = whisper_rs_sys::whisper_full_n_segments_from_state(state);
+            let s0 = n_segments - n_new;
+
+            for i in s0..n_segments {
+                let text = whisper_rs_sys::whisper_full_get_segment_text_from_state(state, i);
+                let text = CStr::from_ptr(text);
+
+                let t0 = whisper_rs_sys::whisper_full_get_segment_t0_from_state(state, i);
+                let t1 = whisper_rs_sys::whisper_full_get_segment_t1_from_state(state, i);
+                if let Ok(n) = text.to_str() {
+                    OwnedCallback::<SegmentCallbackFn>::call(user_data, |f| {
+                        f(SegmentCallbackData {
                             segment: i,
                             start_timestamp: t0,
                             end_timestamp: t1,
                             text: n.to_string(),
-                        }),
-                        Err(_) => {}
-                    }
+                        })
+                    });
                 }
             }
         }
 
         match closure.into() {
             Some(closure) => {
-                // Stable address
-                let closure = Box::new(closure) as SegmentCallbackFn;
-                // Thin pointer
-                let closure = Box::new(closure);
-                // Raw pointer
-                let closure = Box::into_raw(closure);
-
-                self.fp.new_segment_callback_user_data = closure as *mut c_void;
-                self.fp.new_segment_callback = Some(trampoline::<SegmentCallbackFn>);
-                self.segment_calllback_safe = None;
+                let callback = OwnedCallback::new(Box::new(closure) as Box<SegmentCallbackFn>);
+                self.fp.new_segment_callback = Some(trampoline);
+                self.fp.new_segment_callback_user_data = callback.user_data();
+                self.segment_callback_safe = Some(callback);
             }
             None => {
-                self.segment_calllback_safe = None;
                 self.fp.new_segment_callback = None;
                 self.fp.new_segment_callback_user_data = std::ptr::null_mut::<c_void>();
+                self.segment_callback_safe = None;
             }
         }
     }
@@ -476,59 +483,49 @@ impl<'a, 'b> FullParams<'a, 'b> {
     /// Defaults to None.
     pub fn set_segment_callback_safe_lossy<O, F>(&mut self, closure: O)
     where
-        F: FnMut(SegmentCallbackData) + 'static,
+        F: FnMut(SegmentCallbackData) + Send + 'static,
         O: Into<Option<F>>,
     {
         use std::ffi::{c_void, CStr};
         use whisper_rs_sys::{whisper_context, whisper_state};
 
-        extern "C" fn trampoline<F>(
+        unsafe extern "C" fn trampoline(
             _: *mut whisper_context,
             state: *mut whisper_state,
             n_new: i32,
             user_data: *mut c_void,
-        ) where
-            F: FnMut(SegmentCallbackData) + 'static,
-        {
-            unsafe {
-                let user_data = &mut *(user_data as *mut SegmentCallbackFn);
-                let n_segments = whisper_rs_sys::whisper_full_n_segments_from_state(state);
-                let s0 = n_segments - n_new;
-                //let user_data = user_data as *mut Box<dyn FnMut(SegmentCallbackData)>;
-
-                for i in s0..n_segments {
-                    let text = whisper_rs_sys::whisper_full_get_segment_text_from_state(state, i);
-                    let text = CStr::from_ptr(text);
-
-                    let t0 = whisper_rs_sys::whisper_full_get_segment_t0_from_state(state, i);
-                    let t1 = whisper_rs_sys::whisper_full_get_segment_t1_from_state(state, i);
-                    user_data(SegmentCallbackData {
+        ) {
+            let n_segments = whisper_rs_sys::whisper_full_n_segments_from_state(state);
+            let s0 = n_segments - n_new;
+
+            for i in s0..n_segments {
+                let text = whisper_rs_sys::whisper_full_get_segment_text_from_state(state, i);
+                let text = CStr::from_ptr(text);
+
+                let t0 = whisper_rs_sys::whisper_full_get_segment_t0_from_state(state, i);
+                let t1 = whisper_rs_sys::whisper_full_get_segment_t1_from_state(state, i);
+                OwnedCallback::<SegmentCallbackFn>::call(user_data, |f| {
+                    f(SegmentCallbackData {
                         segment: i,
                         start_timestamp: t0,
                         end_timestamp: t1,
                         text: text.to_string_lossy().to_string(),
-                    });
-                }
+                    })
+                });
             }
         }
 
         match closure.into() {
             Some(closure) => {
-                // Stable address
-                let closure = Box::new(closure) as SegmentCallbackFn;
-                // Thin pointer
-                let closure = Box::new(closure);
-                // Raw pointer
-                let closure = Box::into_raw(closure);
-
-                self.fp.new_segment_callback_user_data = closure as *mut c_void;
-                self.fp.new_segment_callback = Some(trampoline::<SegmentCallbackFn>);
-                self.segment_calllback_safe = None;
+                let callback = OwnedCallback::new(Box::new(closure) as Box<SegmentCallbackFn>);
+                self.fp.new_segment_callback = Some(trampoline);
+                self.fp.new_segment_callback_user_data = callback.user_data();
+                self.segment_callback_safe = Some(callback);
             }
             None => {
-                self.segment_calllback_safe = None;
                 self.fp.new_segment_callback = None;
                 self.fp.new_segment_callback_user_data = std::ptr::null_mut::<c_void>();
+                self.segment_callback_safe = None;
             }
         }
     }
@@ -560,32 +557,27 @@ impl<'a, 'b> FullParams<'a, 'b> {
     /// Defaults to None.
     pub fn set_progress_callback_safe<O, F>(&mut self, closure: O)
     where
-        F: FnMut(i32) + 'static,
+        F: FnMut(i32) + Send + 'static,
         O: Into<Option<F>>,
     {
         use std::ffi::c_void;
         use whisper_rs_sys::{whisper_context, whisper_state};
 
-        unsafe extern "C" fn trampoline<F>(
+        unsafe extern "C" fn trampoline(
             _: *mut whisper_context,
             _: *mut whisper_state,
             progress: c_int,
             user_data: *mut c_void,
-        ) where
-            F: FnMut(i32),
-        {
-            let user_data = &mut *(user_data as *mut F);
-            user_data(progress);
+        ) {
+            OwnedCallback::<ProgressCallbackFn>::call(user_data, |f| f(progress));
         }
 
         match closure.into() {
             Some(closure) => {
-                self.fp.progress_callback = Some(trampoline::<Box<dyn FnMut(i32)>>);
-                let boxed_closure = Box::new(closure) as Box<dyn FnMut(i32)>;
-                let boxed_closure = Box::new(boxed_closure);
-                let raw_ptr = Box::into_raw(boxed_closure);
-                self.fp.progress_callback_user_data = raw_ptr as *mut c_void;
-                self.progress_callback_safe = None;
+                let callback = OwnedCallback::new(Box::new(closure) as Box<ProgressCallbackFn>);
+                self.fp.progress_callback = Some(trampoline);
+                self.fp.progress_callback_user_data = callback.user_data();
+                self.progress_callback_safe = Some(callback);
             }
             None => {
                 self.fp.progress_callback = None;
@@ -603,34 +595,27 @@ impl<'a, 'b> FullParams<'a, 'b> {
     /// See `set_progress_callback` if you need to use `whisper_context` and `whisper_state`,
     /// or extend this one to support their use.
     ///
+    /// This replaces any cancellation set with `set_cancellation` or `set_deadline`.
+    ///
     /// Defaults to None.
     pub fn set_abort_callback_safe<O, F>(&mut self, closure: O)
     where
-        F: FnMut() -> bool + 'static,
+        F: FnMut() -> bool + Send + 'static,
         O: Into<Option<F>>,
     {
         use std::ffi::c_void;
 
-        unsafe extern "C" fn trampoline<F>(user_data: *mut c_void) -> bool
-        where
-            F: FnMut() -> bool,
-        {
-            let user_data = &mut *(user_data as *mut F);
-            user_data()
+        unsafe extern "C" fn trampoline(user_data: *mut c_void) -> bool {
+            OwnedCallback::<AbortCallbackFn>::call(user_data, |f| f())
         }
 
+        self.cancellation = Cancellation::default();
         match closure.into() {
             Some(closure) => {
-                // Stable address
-                let closure = Box::new(closure) as Box<dyn FnMut() -> bool>;
-                // Thin pointer
-                let closure = Box::new(closure);
-                // Raw pointer
-                let closure = Box::into_raw(closure);
-
-                self.fp.abort_callback = Some(trampoline::<F>);
-                self.fp.abort_callback_user_data = closure as *mut c_void;
-                self.abort_callback_safe = None;
+                let callback = OwnedCallback::new(Box::new(closure) as Box<AbortCallbackFn>);
+                self.fp.abort_callback = Some(trampoline);
+                self.fp.abort_callback_user_data = callback.user_data();
+                self.abort_callback_safe = Some(callback);
             }
             None => {
                 self.fp.abort_callback = None;
@@ -640,6 +625,34 @@ impl<'a, 'b> FullParams<'a, 'b> {
         }
     }
 
+    /// Cancel the run when `handle` is cancelled, possibly from another thread.
+    ///
+    /// `WhisperState::full` then returns `WhisperError::Aborted`.
+    /// Can be combined with `set_deadline`, but replaces any callback set with `set_abort_callback_safe`.
+    ///
+    /// Defaults to None.
+    pub fn set_cancellation(&mut self, handle: CancellationHandle) {
+        let cancellation = Cancellation::new(Some(handle), self.cancellation.deadline);
+        self.install_cancellation(cancellation);
+    }
+
+    /// Cancel the run once `deadline` has passed.
+    ///
+    /// `WhisperState::full` then returns `WhisperError::Aborted`.
+    /// Can be combined with `set_cancellation`, but replaces any callback set with `set_abort_callback_safe`.
+    ///
+    /// Defaults to None.
+    pub fn set_deadline(&mut self, deadline: Instant) {
+        let cancellation = Cancellation::new(self.cancellation.handle.take(), Some(deadline));
+        self.install_cancellation(cancellation);
+    }
+
+    fn install_cancellation(&mut self, cancellation: Cancellation) {
+        let conditions = cancellation.clone();
+        self.set_abort_callback_safe(move || conditions.check());
+        self.cancellation = cancellation;
+    }
+
     /// Set the user data to be passed to the progress callback.
     ///
     /// # Safety
@@ -668,6 +681,46 @@ impl<'a, 'b> FullParams<'a, 'b> {
         self.fp.encoder_begin_callback = start_encoder_callback;
     }
 
+    /// Set the callback that is called each time before the encoder begins, using a closure.
+    ///
+    /// Return `false` to veto the encoder run, e.g. for rate limiting or budget checks.
+    /// whisper.cpp then stops processing and `WhisperState::full` returns successfully
+    /// with the segments generated so far.
+    ///
+    /// See `set_start_encoder_callback` if you need to use `whisper_context` and `whisper_state`.
+    ///
+    /// Defaults to None.
+    pub fn set_encoder_begin_callback_safe<O, F>(&mut self, closure: O)
+    where
+        F: FnMut() -> bool + Send + 'static,
+        O: Into<Option<F>>,
+    {
+        use std::ffi::c_void;
+        use whisper_rs_sys::{whisper_context, whisper_state};
+
+        unsafe extern "C" fn trampoline(
+            _: *mut whisper_context,
+            _: *mut whisper_state,
+            user_data: *mut c_void,
+        ) -> bool {
+            OwnedCallback::<EncoderBeginCallbackFn>::call(user_data, |f| f())
+        }
+
+        match closure.into() {
+            Some(closure) => {
+                let callback = OwnedCallback::new(Box::new(closure) as Box<EncoderBeginCallbackFn>);
+                self.fp.encoder_begin_callback = Some(trampoline);
+                self.fp.encoder_begin_callback_user_data = callback.user_data();
+                self.encoder_begin_callback_safe = Some(callback);
+            }
+            None => {
+                self.fp.encoder_begin_callback = None;
+                self.fp.encoder_begin_callback_user_data = std::ptr::null_mut::<c_void>();
+                self.encoder_begin_callback_safe = None;
+            }
+        }
+    }
+
     /// Set the user data to be passed to the start encoder callback.
     ///
     /// # Safety
@@ -712,6 +765,58 @@ impl<'a, 'b> FullParams<'a, 'b> {
         self.fp.logits_filter_callback_user_data = user_data;
     }
 
+    /// Set the callback that is called by each decoder to filter obtained logits, using a closure.
+    ///
+    /// The closure receives a [crate::LogitsFilterContext] with the tokens decoded so far
+    /// and vocabulary helpers, and the logits for the next token, one per vocabulary entry.
+    /// Set a logit to `f32::NEG_INFINITY` to suppress that token, or raise it to boost it.
+    ///
+    /// See `set_filter_logits_callback` if you need to use `whisper_context` and `whisper_state`.
+    ///
+    /// Defaults to None.
+    pub fn set_logits_filter_safe<O, F>(&mut self, closure: O)
+    where
+        F: FnMut(crate::LogitsFilterContext, &mut [f32]) + Send + 'static,
+        O: Into<Option<F>>,
+    {
+        use std::ffi::c_void;
+        use whisper_rs_sys::{whisper_context, whisper_state, whisper_token_data};
+
+        unsafe extern "C" fn trampoline(
+            ctx: *mut whisper_context,
+            _: *mut whisper_state,
+            tokens: *const whisper_token_data,
+            n_tokens: c_int,
+            logits: *mut f32,
+            user_data: *mut c_void,
+        ) {
+            let tokens = if tokens.is_null() || n_tokens <= 0 {
+                &[]
+            } else {
+                std::slice::from_raw_parts(tokens, n_tokens as usize)
+            };
+            let n_vocab = whisper_rs_sys::whisper_n_vocab(ctx);
+            let logits = std::slice::from_raw_parts_mut(logits, n_vocab.max(0) as usize);
+            OwnedCallback::<LogitsFilterFn>::call(user_data, |f| {
+                f(crate::LogitsFilterContext::new(ctx, tokens), logits)
+            });
+        }
+
+        match closure.into() {
+            Some(closure) => {
+                let callback = OwnedCallback::new(Box::new(closure) as Box<LogitsFilterFn>);
+                self.fp.logits_filter_callback = Some(trampoline);
+                self.fp.logits_filter_callback_user_data = callback.user_data();
+                self.logits_filter_callback_safe = Some(callback);
+            }
+            None => {
+                self.fp.logits_filter_callback = None;
+                self.fp.logits_filter_callback_user_data = std::ptr::null_mut::<c_void>();
+                self.logits_filter_callback_safe = None;
+            }
+        }
+    }
+
     /// Set the callback that is called each time before ggml computation starts.
     ///
     /// Note that this callback has not been Rustified yet (and likely never will be, unless someone else feels the need to do so).
@@ -777,6 +882,52 @@ impl<'a, 'b> FullParams<'a, 'b> {
         self.fp.grammar_penalty = grammar_penalty;
     }
 
+    /// Remove the new segment and progress callbacks, both safe and unsafe ones.
+    pub(crate) fn clear_segment_and_progress_callbacks(&mut self) {
+        self.fp.new_segment_callback = None;
+        self.fp.new_segment_callback_user_data = std::ptr::null_mut();
+        self.fp.progress_callback = None;
+        self.fp.progress_callback_user_data = std::ptr::null_mut();
+        self.segment_callback_safe = None;
+        self.progress_callback_safe = None;
+    }
+
+    /// Enable whisper.cpp's voice activity detection, so only speech is passed to the model.
+    /// Timestamps in the results still refer to the original audio.
+    ///
+    /// Requires a VAD model to be set with [FullParams::set_vad_model_path].
+    ///
+    /// Defaults to false.
+    #[cfg(feature = "whisper-vad")]
+    pub fn set_vad_enable(&mut self, vad: bool) {
+        self.fp.vad = vad;
+    }
+
+    /// Set the path to the Silero VAD model used when VAD is enabled.
+    /// See [crate::WhisperVadContext] for where to get one.
+    ///
+    /// # Panics
+    /// This method will panic if `path` contains a null byte.
+    ///
+    /// Defaults to None.
+    #[cfg(feature = "whisper-vad")]
+    pub fn set_vad_model_path(&mut self, path: Option<&str>) {
+        self.vad_model_path =
+            path.map(|p| Arc::from(CString::new(p).expect("VAD model path contains null byte")));
+        self.fp.vad_model_path = self
+            .vad_model_path
+            .as_ref()
+            .map_or(std::ptr::null(), |p| p.as_ptr());
+    }
+
+    /// Set the parameters used to find speech when VAD is enabled.
+    ///
+    /// Defaults to [crate::WhisperVadParams::default].
+    #[cfg(feature = "whisper-vad")]
+    pub fn set_vad_params(&mut self, params: crate::WhisperVadParams) {
+        self.fp.vad_params = params.to_c_struct();
+    }
+
     /// Set the initial prompt for the model.
     ///
     /// This is the text that will be used as the starting point for the model's decoding.
@@ -805,6 +956,8 @@ impl<'a, 'b> FullParams<'a, 'b> {
 // following implementations are safe
 // see https://github.com/ggerganov/whisper.cpp/issues/32#issuecomment-1272790388
 // concurrent usage is prevented by &mut self on methods that modify the struct
+// closures set through the safe callback setters are required to be Send,
+// and calls to them are serialized by OwnedCallback
 unsafe impl Send for FullParams<'_, '_> {}
 unsafe impl Sync for FullParams<'_, '_> {}
 
@@ -884,3 +1037,36 @@ mod test_whisper_params_initial_prompt {
         );
     }
 }
+
+#[cfg(test)]
+#[cfg(feature = "test-with-tiny-model")]
+mod test_with_tiny_model {
+    use super::*;
+    use crate::test_util::{load_sample, MODEL_PATH};
+    use crate::{WhisperContext, WhisperContextParameters};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    // These tests expect that the tiny.en model has been downloaded
+    // using the script `sys/whisper.cpp/models/download-ggml-model.sh tiny.en`
+
+    #[test]
+    fn test_encoder_begin_callback_veto() {
+        let ctx = WhisperContext::new_with_params(MODEL_PATH, WhisperContextParameters::default())
+            .expect("Download the ggml-tiny.en model using 'sys/whisper.cpp/models/download-ggml-model.sh tiny.en'");
+        let mut state = ctx.create_state().unwrap();
+        let samples = load_sample();
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let calls_in_callback = Arc::clone(&calls);
+        let mut params = FullParams::new(SamplingStrategy::default());
+        params.set_encoder_begin_callback_safe(move || {
+            calls_in_callback.fetch_add(1, Ordering::Relaxed);
+            false
+        });
+        state.full(params, &samples).unwrap();
+
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+        assert_eq!(state.full_n_segments().unwrap(), 0);
+    }
+}
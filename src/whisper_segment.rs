@@ -0,0 +1,418 @@
+use std::borrow::Cow;
+use std::ffi::c_int;
+use std::iter::FusedIterator;
+
+use crate::{Segment, Token, WhisperError, WhisperState, WhisperToken, WhisperTokenData};
+
+/// Borrowed view of a single segment generated by [WhisperState::full].
+///
+/// Created by iterating over [WhisperState::segments].
+/// No data is copied out of the state unless explicitly requested,
+/// e.g. through [SegmentRef::to_segment].
+#[derive(Debug, Clone, Copy)]
+pub struct SegmentRef<'a> {
+    state: &'a WhisperState,
+    index: c_int,
+}
+
+impl<'a> SegmentRef<'a> {
+    /// Index of this segment in the state.
+    #[inline]
+    pub fn index(&self) -> c_int {
+        self.index
+    }
+
+    /// Start time of this segment, in centiseconds (10 ms units).
+    ///
+    /// # C++ equivalent
+    /// `int64_t whisper_full_get_segment_t0_from_state(struct whisper_state * state, int i_segment)`
+    #[inline]
+    pub fn start(&self) -> i64 {
+        unsafe {
+            whisper_rs_sys::whisper_full_get_segment_t0_from_state(self.state.ptr, self.index)
+        }
+    }
+
+    /// End time of this segment, in centiseconds (10 ms units).
+    ///
+    /// # C++ equivalent
+    /// `int64_t whisper_full_get_segment_t1_from_state(struct whisper_state * state, int i_segment)`
+    #[inline]
+    pub fn end(&self) -> i64 {
+        unsafe {
+            whisper_rs_sys::whisper_full_get_segment_t1_from_state(self.state.ptr, self.index)
+        }
+    }
+
+    /// Raw bytes of this segment's text.
+    ///
+    /// # Returns
+    /// `Ok(&[u8])` on success, or `Err(WhisperError::NullPointer)` on failure
+    /// (this is the only possible error)
+    pub fn bytes(&self) -> Result<&'a [u8], WhisperError> {
+        Ok(self.state.full_get_segment_raw(self.index)?.to_bytes())
+    }
+
+    /// Text of this segment.
+    ///
+    /// # Returns
+    /// `Ok(&str)` on success, or `Err(WhisperError)` on failure (either `NullPointer` or `InvalidUtf8`)
+    pub fn text(&self) -> Result<&'a str, WhisperError> {
+        Ok(self.state.full_get_segment_raw(self.index)?.to_str()?)
+    }
+
+    /// Text of this segment, replacing invalid UTF-8 with the replacement character.
+    ///
+    /// # Returns
+    /// `Ok(Cow<str>)` on success, or `Err(WhisperError::NullPointer)` on failure
+    /// (this is the only possible error)
+    pub fn text_lossy(&self) -> Result<Cow<'a, str>, WhisperError> {
+        Ok(self
+            .state
+            .full_get_segment_raw(self.index)?
+            .to_string_lossy())
+    }
+
+    /// Whether the next segment is predicted as a speaker turn.
+    #[inline]
+    pub fn speaker_turn_next(&self) -> bool {
+        self.state.full_get_segment_speaker_turn_next(self.index)
+    }
+
+    /// Probability that this segment contains no speech.
+    #[cfg(feature = "no-speech-prob")]
+    #[inline]
+    pub fn no_speech_prob(&self) -> f32 {
+        self.state.full_get_segment_no_speech_prob(self.index)
+    }
+
+    /// Number of tokens in this segment.
+    #[inline]
+    pub fn n_tokens(&self) -> c_int {
+        unsafe { whisper_rs_sys::whisper_full_n_tokens_from_state(self.state.ptr, self.index) }
+    }
+
+    /// Iterate over the tokens of this segment.
+    pub fn tokens(&self) -> TokenIter<'a> {
+        TokenIter {
+            state: self.state,
+            segment: self.index,
+            front: 0,
+            back: self.n_tokens().max(0),
+        }
+    }
+
+    /// Copy this segment and all of its tokens into an owned [Segment].
+    pub fn to_segment(&self) -> Result<Segment, WhisperError> {
+        Ok(Segment {
+            t0: self.start(),
+            t1: self.end(),
+            text: self.text_lossy()?.into_owned(),
+            speaker_turn_next: self.speaker_turn_next(),
+            #[cfg(feature = "no-speech-prob")]
+            no_speech_prob: self.no_speech_prob(),
+            tokens: self
+                .tokens()
+                .map(|t| t.to_token())
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+/// Borrowed view of a single token in a [SegmentRef].
+#[derive(Debug, Clone, Copy)]
+pub struct TokenRef<'a> {
+    state: &'a WhisperState,
+    segment: c_int,
+    index: c_int,
+}
+
+impl<'a> TokenRef<'a> {
+    /// Index of the segment this token belongs to.
+    #[inline]
+    pub fn segment_index(&self) -> c_int {
+        self.segment
+    }
+
+    /// Index of this token in its segment.
+    #[inline]
+    pub fn index(&self) -> c_int {
+        self.index
+    }
+
+    /// ID of this token.
+    ///
+    /// # C++ equivalent
+    /// `whisper_token whisper_full_get_token_id_from_state(struct whisper_state * state, int i_segment, int i_token)`
+    #[inline]
+    pub fn id(&self) -> WhisperToken {
+        unsafe {
+            whisper_rs_sys::whisper_full_get_token_id_from_state(
+                self.state.ptr,
+                self.segment,
+                self.index,
+            )
+        }
+    }
+
+    /// Probability of this token.
+    ///
+    /// # C++ equivalent
+    /// `float whisper_full_get_token_p_from_state(struct whisper_state * state, int i_segment, int i_token)`
+    #[inline]
+    pub fn prob(&self) -> f32 {
+        unsafe {
+            whisper_rs_sys::whisper_full_get_token_p_from_state(
+                self.state.ptr,
+                self.segment,
+                self.index,
+            )
+        }
+    }
+
+    /// Full token data of this token.
+    ///
+    /// # C++ equivalent
+    /// `whisper_token_data whisper_full_get_token_data_from_state(struct whisper_state * state, int i_segment, int i_token)`
+    #[inline]
+    pub fn data(&self) -> WhisperTokenData {
+        unsafe {
+            whisper_rs_sys::whisper_full_get_token_data_from_state(
+                self.state.ptr,
+                self.segment,
+                self.index,
+            )
+        }
+    }
+
+    /// Raw bytes of this token.
+    ///
+    /// Useful if you're using a language for which whisper is known to split tokens
+    /// away from UTF-8 character boundaries.
+    pub fn bytes(&self) -> Result<&'a [u8], WhisperError> {
+        Ok(self
+            .state
+            .full_get_token_raw(self.segment, self.index)?
+            .to_bytes())
+    }
+
+    /// Text of this token.
+    ///
+    /// # Returns
+    /// `Ok(&str)` on success, or `Err(WhisperError)` on failure (either `NullPointer` or `InvalidUtf8`)
+    pub fn text(&self) -> Result<&'a str, WhisperError> {
+        Ok(self
+            .state
+            .full_get_token_raw(self.segment, self.index)?
+            .to_str()?)
+    }
+
+    /// Text of this token, replacing invalid UTF-8 with the replacement character.
+    pub fn text_lossy(&self) -> Result<Cow<'a, str>, WhisperError> {
+        Ok(self
+            .state
+            .full_get_token_raw(self.segment, self.index)?
+            .to_string_lossy())
+    }
+
+    /// Copy this token into an owned [Token].
+    pub fn to_token(&self) -> Result<Token, WhisperError> {
+        Ok(Token::new(self.data(), self.text_lossy()?.into_owned()))
+    }
+}
+
+/// Iterator over the segments of a [WhisperState], created by [WhisperState::segments].
+#[derive(Debug, Clone)]
+pub struct SegmentIter<'a> {
+    state: &'a WhisperState,
+    front: c_int,
+    back: c_int,
+}
+
+impl<'a> SegmentIter<'a> {
+    pub(crate) fn new(state: &'a WhisperState, n_segments: c_int) -> Self {
+        Self {
+            state,
+            front: 0,
+            back: n_segments.max(0),
+        }
+    }
+}
+
+impl<'a> Iterator for SegmentIter<'a> {
+    type Item = SegmentRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front >= self.back {
+            return None;
+        }
+        let index = self.front;
+        self.front += 1;
+        Some(SegmentRef {
+            state: self.state,
+            index,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.back - self.front) as usize;
+        (len, Some(len))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        // clamp before casting, a large n would otherwise wrap around
+        let n = n.min((self.back - self.front) as usize);
+        self.front += n as c_int;
+        self.next()
+    }
+}
+
+impl DoubleEndedIterator for SegmentIter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front >= self.back {
+            return None;
+        }
+        self.back -= 1;
+        Some(SegmentRef {
+            state: self.state,
+            index: self.back,
+        })
+    }
+}
+
+impl ExactSizeIterator for SegmentIter<'_> {}
+
+impl FusedIterator for SegmentIter<'_> {}
+
+/// Iterator over the tokens of a [SegmentRef], created by [SegmentRef::tokens].
+#[derive(Debug, Clone)]
+pub struct TokenIter<'a> {
+    state: &'a WhisperState,
+    segment: c_int,
+    front: c_int,
+    back: c_int,
+}
+
+impl<'a> Iterator for TokenIter<'a> {
+    type Item = TokenRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front >= self.back {
+            return None;
+        }
+        let index = self.front;
+        self.front += 1;
+        Some(TokenRef {
+            state: self.state,
+            segment: self.segment,
+            index,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.back - self.front) as usize;
+        (len, Some(len))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        // clamp before casting, a large n would otherwise wrap around
+        let n = n.min((self.back - self.front) as usize);
+        self.front += n as c_int;
+        self.next()
+    }
+}
+
+impl DoubleEndedIterator for TokenIter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front >= self.back {
+            return None;
+        }
+        self.back -= 1;
+        Some(TokenRef {
+            state: self.state,
+            segment: self.segment,
+            index: self.back,
+        })
+    }
+}
+
+impl ExactSizeIterator for TokenIter<'_> {}
+
+impl FusedIterator for TokenIter<'_> {}
+
+#[cfg(test)]
+#[cfg(feature = "test-with-tiny-model")]
+mod test_with_tiny_model {
+    use crate::test_util::{load_sample, MODEL_PATH};
+    use crate::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+    // These tests expect that the tiny.en model has been downloaded
+    // using the script `sys/whisper.cpp/models/download-ggml-model.sh tiny.en`
+
+    #[test]
+    fn test_segment_iter_matches_accessors() {
+        let ctx = WhisperContext::new_with_params(MODEL_PATH, WhisperContextParameters::default())
+            .expect("Download the ggml-tiny.en model using 'sys/whisper.cpp/models/download-ggml-model.sh tiny.en'");
+        let mut state = ctx.create_state().unwrap();
+        let samples = load_sample();
+        state
+            .full(FullParams::new(SamplingStrategy::default()), &samples)
+            .unwrap();
+
+        let segments = state.segments();
+        assert_eq!(segments.len(), state.full_n_segments().unwrap() as usize);
+        for segment in segments {
+            let i = segment.index();
+            assert_eq!(segment.start(), state.full_get_segment_t0(i).unwrap());
+            assert_eq!(segment.end(), state.full_get_segment_t1(i).unwrap());
+            assert_eq!(
+                segment.text_lossy().unwrap(),
+                state.full_get_segment_text_lossy(i).unwrap()
+            );
+            assert_eq!(
+                segment.tokens().len(),
+                state.full_n_tokens(i).unwrap() as usize
+            );
+            for token in segment.tokens() {
+                assert_eq!(
+                    token.id(),
+                    state.full_get_token_id(i, token.index()).unwrap()
+                );
+            }
+        }
+
+        let forward = state.segments().map(|s| s.index()).collect::<Vec<_>>();
+        let mut backward = state
+            .segments()
+            .rev()
+            .map(|s| s.index())
+            .collect::<Vec<_>>();
+        backward.reverse();
+        assert_eq!(forward, backward);
+    }
+
+    #[test]
+    fn test_nth_past_the_end() {
+        let ctx = WhisperContext::new_with_params(MODEL_PATH, WhisperContextParameters::default())
+            .expect("Download the ggml-tiny.en model using 'sys/whisper.cpp/models/download-ggml-model.sh tiny.en'");
+        let mut state = ctx.create_state().unwrap();
+        assert!(state.segments().nth(usize::MAX).is_none());
+
+        let samples = load_sample();
+        state
+            .full(FullParams::new(SamplingStrategy::default()), &samples)
+            .unwrap();
+
+        let mut segments = state.segments();
+        assert!(segments.nth(usize::MAX).is_none());
+        assert_eq!(segments.len(), 0);
+        // would wrap around to 0 when cast to a c_int
+        assert!(state.segments().nth(1 << 32).is_none());
+
+        let segment = state.segments().next().unwrap();
+        let mut tokens = segment.tokens();
+        assert!(tokens.nth(usize::MAX).is_none());
+        assert_eq!(tokens.len(), 0);
+        assert!(segment.tokens().nth(1 << 32).is_none());
+    }
+}
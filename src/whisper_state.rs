@@ -1,13 +1,19 @@
 use std::ffi::{c_int, CStr};
 use std::sync::Arc;
+use std::time::Instant;
 
-use crate::{FullParams, WhisperError, WhisperInnerContext, WhisperToken, WhisperTokenData};
+use crate::common_logging::generic_debug;
+use crate::{
+    FullParams, RunTimings, SegmentIter, Transcript, WhisperError, WhisperInnerContext,
+    WhisperToken, WhisperTokenData,
+};
 
 /// Rustified pointer to a Whisper state.
 #[derive(Debug)]
 pub struct WhisperState {
     ctx: Arc<WhisperInnerContext>,
-    ptr: *mut whisper_rs_sys::whisper_state,
+    pub(crate) ptr: *mut whisper_rs_sys::whisper_state,
+    last_run: Option<RunTimings>,
 }
 
 unsafe impl Send for WhisperState {}
@@ -27,7 +33,11 @@ impl WhisperState {
         ctx: Arc<WhisperInnerContext>,
         ptr: *mut whisper_rs_sys::whisper_state,
     ) -> Self {
-        Self { ctx, ptr }
+        Self {
+            ctx,
+            ptr,
+            last_run: None,
+        }
     }
 
     /// Convert raw PCM audio (floating point 32 bit) to log mel spectrogram.
@@ -272,6 +282,8 @@ impl WhisperState {
     ///
     /// # Returns
     /// Ok(c_int) on success, Err(WhisperError) on failure.
+    /// Err(WhisperError::Aborted) if the run was cancelled with [crate::FullParams::set_cancellation]
+    /// or [crate::FullParams::set_deadline].
     ///
     /// # C++ equivalent
     /// `int whisper_full(struct whisper_context * ctx, struct whisper_full_params params, const float * samples, int n_samples)`
@@ -280,7 +292,11 @@ impl WhisperState {
             // can randomly trigger segmentation faults if we don't check this
             return Err(WhisperError::NoSamples);
         }
+        if params.cancellation.is_aborted() {
+            return Err(WhisperError::Aborted);
+        }
 
+        let start = Instant::now();
         let ret = unsafe {
             whisper_rs_sys::whisper_full_with_state(
                 self.ctx.ctx,
@@ -290,7 +306,19 @@ impl WhisperState {
                 data.len() as c_int,
             )
         };
-        if ret == -1 {
+        let timings = RunTimings::new(data.len(), start.elapsed());
+        self.last_run = Some(timings);
+        generic_debug!(
+            "whisper_full: transcribed {:.2?} of audio in {:.2?} (real time factor {:.3})",
+            timings.audio,
+            timings.elapsed,
+            timings.real_time_factor()
+        );
+        if ret != 0 && params.cancellation.was_triggered() {
+            // whisper.cpp reports an abort as a failure of whichever step was running,
+            // so only trust the abort callback having actually stopped it
+            Err(WhisperError::Aborted)
+        } else if ret == -1 {
             Err(WhisperError::UnableToCalculateSpectrogram)
         } else if ret == 7 {
             Err(WhisperError::FailedToEncode)
@@ -303,6 +331,22 @@ impl WhisperState {
         }
     }
 
+    /// Wall-clock time of the last call to [Self::full] on this state,
+    /// whether it succeeded or not.
+    ///
+    /// Returns None if [Self::full] has not been called yet.
+    /// With the `log_backend` or `tracing_backend` feature, every run is also logged at debug level.
+    ///
+    /// This does not break the run down into sampling, encoding and decoding.
+    /// whisper.cpp only keeps that breakdown for the default state of a context,
+    /// read with `whisper_get_timings`, and has no function to read it for any other state.
+    /// Contexts created by whisper-rs have no default state, as every run uses its own [WhisperState],
+    /// so `whisper_get_timings` would always return null.
+    #[inline]
+    pub fn last_run_timings(&self) -> Option<RunTimings> {
+        self.last_run
+    }
+
     /// Number of generated text segments.
     /// A segment can be a few words, a sentence, or even a paragraph.
     ///
@@ -346,7 +390,7 @@ impl WhisperState {
         Ok(unsafe { whisper_rs_sys::whisper_full_get_segment_t1_from_state(self.ptr, segment) })
     }
 
-    fn full_get_segment_raw(&self, segment: c_int) -> Result<&CStr, WhisperError> {
+    pub(crate) fn full_get_segment_raw(&self, segment: c_int) -> Result<&CStr, WhisperError> {
         let ret =
             unsafe { whisper_rs_sys::whisper_full_get_segment_text_from_state(self.ptr, segment) };
         if ret.is_null() {
@@ -421,7 +465,11 @@ impl WhisperState {
         Ok(unsafe { whisper_rs_sys::whisper_full_n_tokens_from_state(self.ptr, segment) })
     }
 
-    fn full_get_token_raw(&self, segment: c_int, token: c_int) -> Result<&CStr, WhisperError> {
+    pub(crate) fn full_get_token_raw(
+        &self,
+        segment: c_int,
+        token: c_int,
+    ) -> Result<&CStr, WhisperError> {
         let ret = unsafe {
             whisper_rs_sys::whisper_full_get_token_text_from_state(
                 self.ctx.ctx,
@@ -581,11 +629,71 @@ impl WhisperState {
     ///
     /// # C++ equivalent
     /// `bool whisper_full_get_segment_speaker_turn_next_from_state(struct whisper_state * state, int i_segment)`
-    pub fn full_get_segment_speaker_turn_next(&mut self, i_segment: c_int) -> bool {
+    pub fn full_get_segment_speaker_turn_next(&self, i_segment: c_int) -> bool {
         unsafe {
             whisper_rs_sys::whisper_full_get_segment_speaker_turn_next_from_state(
                 self.ptr, i_segment,
             )
         }
     }
+
+    /// Get the probability that the specified segment contains no speech.
+    ///
+    /// # Arguments
+    /// * i_segment: Segment index.
+    ///
+    /// # Returns
+    /// f32
+    ///
+    /// # C++ equivalent
+    /// `float whisper_full_get_segment_no_speech_prob_from_state(struct whisper_state * state, int i_segment)`
+    #[cfg(feature = "no-speech-prob")]
+    #[inline]
+    pub fn full_get_segment_no_speech_prob(&self, i_segment: c_int) -> f32 {
+        unsafe {
+            whisper_rs_sys::whisper_full_get_segment_no_speech_prob_from_state(self.ptr, i_segment)
+        }
+    }
+
+    /// Iterate over the segments generated by the last call to [WhisperState::full].
+    ///
+    /// The returned [SegmentRef]s borrow the state, so no data is copied unless asked for.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use whisper_rs::WhisperState;
+    /// # fn print(state: &WhisperState) -> Result<(), whisper_rs::WhisperError> {
+    /// for segment in state.segments() {
+    ///     println!("[{} - {}]: {}", segment.start(), segment.end(), segment.text()?);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn segments(&self) -> SegmentIter<'_> {
+        let n_segments = unsafe { whisper_rs_sys::whisper_full_n_segments_from_state(self.ptr) };
+        SegmentIter::new(self, n_segments)
+    }
+
+    /// Collect the results of the last call to [WhisperState::full] into an owned [Transcript].
+    ///
+    /// The returned value does not borrow the state, so it can outlive it,
+    /// be sent across threads, and be compared against other transcripts.
+    ///
+    /// Segment and token text is converted lossily, see [WhisperState::full_get_segment_text_lossy].
+    ///
+    /// # Returns
+    /// `Ok(Transcript)` on success, `Err(WhisperError)` on failure.
+    pub fn transcript(&self) -> Result<Transcript, WhisperError> {
+        let lang_id = self.full_lang_id_from_state()?;
+        let segments = self
+            .segments()
+            .map(|s| s.to_segment())
+            .collect::<Result<_, _>>()?;
+
+        Ok(Transcript {
+            lang_id,
+            language: crate::get_lang_str(lang_id).map(str::to_string),
+            segments,
+        })
+    }
 }
@@ -0,0 +1,77 @@
+//! Performance statistics of individual runs of [crate::WhisperState::full].
+
+use std::time::Duration;
+
+use crate::WHISPER_SAMPLE_RATE;
+
+/// Wall-clock time of a single call to [crate::WhisperState::full].
+///
+/// See [crate::WhisperState::last_run_timings].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunTimings {
+    /// Duration of the transcribed audio.
+    pub audio: Duration,
+    /// Time it took to transcribe it.
+    pub elapsed: Duration,
+}
+
+impl RunTimings {
+    pub(crate) fn new(n_samples: usize, elapsed: Duration) -> Self {
+        Self {
+            audio: Duration::from_secs_f64(n_samples as f64 / WHISPER_SAMPLE_RATE as f64),
+            elapsed,
+        }
+    }
+
+    /// Time spent per second of audio. Below 1.0 is faster than real time.
+    ///
+    /// Returns 0.0 if no audio was transcribed.
+    pub fn real_time_factor(&self) -> f64 {
+        if self.audio.is_zero() {
+            0.0
+        } else {
+            self.elapsed.as_secs_f64() / self.audio.as_secs_f64()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_real_time_factor() {
+        let timings = RunTimings::new(WHISPER_SAMPLE_RATE as usize * 10, Duration::from_secs(2));
+        assert_eq!(timings.audio, Duration::from_secs(10));
+        assert_eq!(timings.real_time_factor(), 0.2);
+
+        let timings = RunTimings::new(0, Duration::from_millis(5));
+        assert_eq!(timings.real_time_factor(), 0.0);
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "test-with-tiny-model")]
+mod test_with_tiny_model {
+    use crate::test_util::MODEL_PATH;
+    use crate::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+    // These tests expect that the tiny.en model has been downloaded
+    // using the script `sys/whisper.cpp/models/download-ggml-model.sh tiny.en`
+
+    #[test]
+    fn test_last_run_timings() {
+        let ctx = WhisperContext::new_with_params(MODEL_PATH, WhisperContextParameters::default())
+            .expect("Download the ggml-tiny.en model using 'sys/whisper.cpp/models/download-ggml-model.sh tiny.en'");
+        let mut state = ctx.create_state().unwrap();
+        assert!(state.last_run_timings().is_none());
+
+        let samples = vec![0.0f32; crate::WHISPER_SAMPLE_RATE as usize * 2];
+        state
+            .full(FullParams::new(SamplingStrategy::default()), &samples)
+            .unwrap();
+        let timings = state.last_run_timings().unwrap();
+        assert_eq!(timings.audio.as_secs(), 2);
+        assert!(timings.real_time_factor() > 0.0);
+    }
+}
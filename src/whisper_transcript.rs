@@ -0,0 +1,154 @@
+use std::ffi::c_int;
+
+use crate::{WhisperToken, WhisperTokenData};
+
+/// Owned results of a call to [crate::WhisperState::full].
+///
+/// Created with [crate::WhisperState::transcript].
+/// Unlike the `full_get_*` accessors on [crate::WhisperState], this does not borrow the state,
+/// so it can outlive it, be sent across threads, and be compared in tests.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Transcript {
+    /// ID of the language that was used for decoding. -1 if unknown.
+    pub lang_id: c_int,
+    /// Short string of the language that was used for decoding (e.g. "en"), if known.
+    pub language: Option<String>,
+    /// All segments generated by the run, in order.
+    pub segments: Vec<Segment>,
+}
+
+impl Transcript {
+    /// Concatenate the text of all segments.
+    pub fn text(&self) -> String {
+        self.segments.iter().map(|s| s.text.as_str()).collect()
+    }
+}
+
+/// A single segment of a [Transcript].
+///
+/// A segment can be a few words, a sentence, or even a paragraph.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Segment {
+    /// Start time of the segment, in centiseconds (10 ms units).
+    pub t0: i64,
+    /// End time of the segment, in centiseconds (10 ms units).
+    pub t1: i64,
+    /// Text of the segment. Invalid UTF-8 is replaced with the replacement character.
+    pub text: String,
+    /// Whether the next segment is predicted as a speaker turn (requires tinydiarize).
+    pub speaker_turn_next: bool,
+    /// Probability that this segment contains no speech.
+    #[cfg(feature = "no-speech-prob")]
+    pub no_speech_prob: f32,
+    /// Tokens making up this segment, including special tokens.
+    pub tokens: Vec<Token>,
+}
+
+impl Segment {
+    /// Shift all timestamps of this segment and its tokens by `offset` centiseconds,
+    /// e.g. to turn timestamps relative to a window of audio into absolute ones.
+    pub(crate) fn shift(&mut self, offset: i64) {
+        self.t0 += offset;
+        self.t1 += offset;
+        for token in &mut self.tokens {
+            token.t0 += offset;
+            token.t1 += offset;
+            if token.t_dtw >= 0 {
+                token.t_dtw += offset;
+            }
+        }
+    }
+}
+
+/// A single token of a [Segment].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Token {
+    /// Token ID.
+    pub id: WhisperToken,
+    /// Forced timestamp token ID.
+    pub tid: WhisperToken,
+    /// Text of the token. Invalid UTF-8 is replaced with the replacement character,
+    /// as whisper may split tokens away from UTF-8 character boundaries.
+    pub text: String,
+    /// Probability of the token.
+    pub p: f32,
+    /// Log probability of the token.
+    pub plog: f32,
+    /// Probability of the timestamp token.
+    pub pt: f32,
+    /// Sum of probabilities of all timestamp tokens.
+    pub ptsum: f32,
+    /// Start time of the token, in centiseconds. Only valid with token timestamps enabled.
+    pub t0: i64,
+    /// End time of the token, in centiseconds. Only valid with token timestamps enabled.
+    pub t1: i64,
+    /// DTW timestamp of the token, in centiseconds. -1 if DTW is disabled.
+    pub t_dtw: i64,
+    /// Voice length of the token.
+    pub vlen: f32,
+}
+
+impl Token {
+    pub(crate) fn new(data: WhisperTokenData, text: String) -> Self {
+        Self {
+            id: data.id,
+            tid: data.tid,
+            text,
+            p: data.p,
+            plog: data.plog,
+            pt: data.pt,
+            ptsum: data.ptsum,
+            t0: data.t0,
+            t1: data.t1,
+            t_dtw: data.t_dtw,
+            vlen: data.vlen,
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "test-with-tiny-model")]
+mod test_with_tiny_model {
+    use crate::test_util::{load_sample, MODEL_PATH};
+    use crate::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+    // These tests expect that the tiny.en model has been downloaded
+    // using the script `sys/whisper.cpp/models/download-ggml-model.sh tiny.en`
+
+    #[test]
+    fn test_transcript_matches_accessors() {
+        let ctx = WhisperContext::new_with_params(MODEL_PATH, WhisperContextParameters::default())
+            .expect("Download the ggml-tiny.en model using 'sys/whisper.cpp/models/download-ggml-model.sh tiny.en'");
+        let mut state = ctx.create_state().unwrap();
+        state
+            .full(FullParams::new(SamplingStrategy::default()), &load_sample())
+            .unwrap();
+
+        let transcript = state.transcript().unwrap();
+        assert_eq!(
+            transcript.segments.len(),
+            state.full_n_segments().unwrap() as usize
+        );
+        for (i, segment) in transcript.segments.iter().enumerate() {
+            let i = i as i32;
+            assert_eq!(segment.t0, state.full_get_segment_t0(i).unwrap());
+            assert_eq!(segment.t1, state.full_get_segment_t1(i).unwrap());
+            assert_eq!(segment.text, state.full_get_segment_text_lossy(i).unwrap());
+            assert_eq!(
+                segment.tokens.len(),
+                state.full_n_tokens(i).unwrap() as usize
+            );
+        }
+
+        // the transcript must be usable after the state is gone
+        drop(state);
+        let cloned = transcript.clone();
+        assert_eq!(cloned, transcript);
+        std::thread::spawn(move || assert!(!cloned.text().is_empty()))
+            .join()
+            .unwrap();
+    }
+}
@@ -0,0 +1,452 @@
+use std::ffi::{c_int, CString};
+use std::io::Read;
+
+use crate::vad::SpeechSpan;
+use crate::whisper_model_loader::ModelLoader;
+use crate::{WhisperError, WHISPER_SAMPLE_RATE};
+
+/// Parameters for loading a [WhisperVadContext].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WhisperVadContextParams {
+    /// Number of threads to run the model on, default 4
+    pub n_threads: c_int,
+    /// Use GPU if available, default false
+    pub use_gpu: bool,
+    /// GPU device id, default 0
+    pub gpu_device: c_int,
+}
+
+impl Default for WhisperVadContextParams {
+    fn default() -> Self {
+        Self {
+            n_threads: 4,
+            use_gpu: false,
+            gpu_device: 0,
+        }
+    }
+}
+
+impl WhisperVadContextParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn n_threads(&mut self, n_threads: c_int) -> &mut Self {
+        self.n_threads = n_threads;
+        self
+    }
+    pub fn use_gpu(&mut self, use_gpu: bool) -> &mut Self {
+        self.use_gpu = use_gpu;
+        self
+    }
+    pub fn gpu_device(&mut self, gpu_device: c_int) -> &mut Self {
+        self.gpu_device = gpu_device;
+        self
+    }
+
+    fn to_c_struct(self) -> whisper_rs_sys::whisper_vad_context_params {
+        whisper_rs_sys::whisper_vad_context_params {
+            n_threads: self.n_threads,
+            use_gpu: self.use_gpu,
+            gpu_device: self.gpu_device,
+        }
+    }
+}
+
+/// Parameters for turning speech probabilities into speech segments,
+/// used by [WhisperVadContext] and [crate::FullParams::set_vad_params].
+///
+/// The defaults match whisper.cpp's `whisper_vad_default_params`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WhisperVadParams {
+    /// Probability above which a frame is considered speech, default 0.5
+    pub threshold: f32,
+    /// Speech shorter than this is dropped, in milliseconds, default 250
+    pub min_speech_duration_ms: c_int,
+    /// Silence shorter than this does not end a speech segment, in milliseconds, default 100
+    pub min_silence_duration_ms: c_int,
+    /// Speech segments longer than this are split, in seconds, default f32::MAX
+    pub max_speech_duration_s: f32,
+    /// Padding added before and after each speech segment, in milliseconds, default 30
+    pub speech_pad_ms: c_int,
+    /// Overlap between speech segments when they are copied out of the audio, in seconds,
+    /// default 0.1
+    pub samples_overlap: f32,
+}
+
+impl Default for WhisperVadParams {
+    fn default() -> Self {
+        Self {
+            threshold: 0.5,
+            min_speech_duration_ms: 250,
+            min_silence_duration_ms: 100,
+            max_speech_duration_s: f32::MAX,
+            speech_pad_ms: 30,
+            samples_overlap: 0.1,
+        }
+    }
+}
+
+impl WhisperVadParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn threshold(&mut self, threshold: f32) -> &mut Self {
+        self.threshold = threshold;
+        self
+    }
+    pub fn min_speech_duration_ms(&mut self, min_speech_duration_ms: c_int) -> &mut Self {
+        self.min_speech_duration_ms = min_speech_duration_ms;
+        self
+    }
+    pub fn min_silence_duration_ms(&mut self, min_silence_duration_ms: c_int) -> &mut Self {
+        self.min_silence_duration_ms = min_silence_duration_ms;
+        self
+    }
+    pub fn max_speech_duration_s(&mut self, max_speech_duration_s: f32) -> &mut Self {
+        self.max_speech_duration_s = max_speech_duration_s;
+        self
+    }
+    pub fn speech_pad_ms(&mut self, speech_pad_ms: c_int) -> &mut Self {
+        self.speech_pad_ms = speech_pad_ms;
+        self
+    }
+    pub fn samples_overlap(&mut self, samples_overlap: f32) -> &mut Self {
+        self.samples_overlap = samples_overlap;
+        self
+    }
+
+    pub(crate) fn to_c_struct(self) -> whisper_rs_sys::whisper_vad_params {
+        whisper_rs_sys::whisper_vad_params {
+            threshold: self.threshold,
+            min_speech_duration_ms: self.min_speech_duration_ms,
+            min_silence_duration_ms: self.min_silence_duration_ms,
+            max_speech_duration_s: self.max_speech_duration_s,
+            speech_pad_ms: self.speech_pad_ms,
+            samples_overlap: self.samples_overlap,
+        }
+    }
+}
+
+/// A segment of speech found by [WhisperVadContext].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WhisperVadSegment {
+    /// Start of the segment, in centiseconds (10 ms units).
+    pub t0: f32,
+    /// End of the segment, in centiseconds (10 ms units).
+    pub t1: f32,
+}
+
+impl WhisperVadSegment {
+    /// Convert this segment to a span of 16 kHz samples,
+    /// e.g. to pass to [crate::vad::transcribe_speech].
+    pub fn to_speech_span(&self) -> SpeechSpan {
+        let samples_per_cs = WHISPER_SAMPLE_RATE as f32 / 100.0;
+        SpeechSpan {
+            start: (self.t0 * samples_per_cs).max(0.0) as usize,
+            end: (self.t1 * samples_per_cs).max(0.0) as usize,
+        }
+    }
+}
+
+/// whisper.cpp's native voice activity detection, running a Silero VAD model.
+///
+/// Models can be downloaded with the script
+/// `sys/whisper.cpp/models/download-vad-model.sh silero-v5.1.2`.
+///
+/// To have [crate::WhisperState::full] skip silence on its own,
+/// use [crate::FullParams::set_vad_enable] instead.
+///
+/// Requires the `whisper-vad` feature, and a whisper.cpp with the VAD API to generate bindings from.
+#[derive(Debug)]
+pub struct WhisperVadContext {
+    ptr: *mut whisper_rs_sys::whisper_vad_context,
+}
+
+impl WhisperVadContext {
+    /// Load a VAD model from a file.
+    ///
+    /// # Arguments
+    /// * path: The path to the model file.
+    /// * params: The parameters to load the model with.
+    ///
+    /// # Returns
+    /// Ok(Self) on success, Err(WhisperError) on failure.
+    ///
+    /// # C++ equivalent
+    /// `struct whisper_vad_context * whisper_vad_init_from_file_with_params(const char * path_model, struct whisper_vad_context_params params);`
+    pub fn new(path: &str, params: WhisperVadContextParams) -> Result<Self, WhisperError> {
+        let path_cstr = CString::new(path)?;
+        let ptr = unsafe {
+            whisper_rs_sys::whisper_vad_init_from_file_with_params(
+                path_cstr.as_ptr(),
+                params.to_c_struct(),
+            )
+        };
+        Self::from_ptr(ptr)
+    }
+
+    /// Load a VAD model from a buffer.
+    ///
+    /// # Arguments
+    /// * buffer: The buffer containing the model.
+    /// * params: The parameters to load the model with.
+    ///
+    /// # Returns
+    /// Ok(Self) on success, Err(WhisperError) on failure.
+    ///
+    /// # C++ equivalent
+    /// `struct whisper_vad_context * whisper_vad_init_with_params(struct whisper_model_loader * loader, struct whisper_vad_context_params params);`
+    pub fn new_from_buffer(
+        buffer: &[u8],
+        params: WhisperVadContextParams,
+    ) -> Result<Self, WhisperError> {
+        Self::new_from_reader(buffer, params)
+    }
+
+    /// Load a VAD model from any reader, such as a decompressing or decrypting one.
+    ///
+    /// The model is read front to back once, straight into the model's own buffers.
+    ///
+    /// # Arguments
+    /// * reader: The reader to load the model from.
+    /// * params: The parameters to load the model with.
+    ///
+    /// # Returns
+    /// Ok(Self) on success, Err(WhisperError) on failure.
+    /// Err(WhisperError::FailedToReadModel) if reading failed.
+    ///
+    /// # C++ equivalent
+    /// `struct whisper_vad_context * whisper_vad_init_with_params(struct whisper_model_loader * loader, struct whisper_vad_context_params params);`
+    pub fn new_from_reader<R: Read>(
+        reader: R,
+        params: WhisperVadContextParams,
+    ) -> Result<Self, WhisperError> {
+        let ptr = ModelLoader::new(reader).load(
+            |loader| unsafe {
+                whisper_rs_sys::whisper_vad_init_with_params(loader, params.to_c_struct())
+            },
+            whisper_rs_sys::whisper_vad_free,
+        )?;
+        Ok(Self { ptr })
+    }
+
+    fn from_ptr(ptr: *mut whisper_rs_sys::whisper_vad_context) -> Result<Self, WhisperError> {
+        if ptr.is_null() {
+            Err(WhisperError::InitError)
+        } else {
+            Ok(Self { ptr })
+        }
+    }
+
+    /// Run the model on `samples`, computing the probability of speech for each frame.
+    /// The results can be read with [WhisperVadContext::probabilities].
+    ///
+    /// # Arguments
+    /// * samples: 16 kHz mono audio samples.
+    ///
+    /// # C++ equivalent
+    /// `bool whisper_vad_detect_speech(struct whisper_vad_context * vctx, const float * samples, int n_samples);`
+    pub fn detect_speech(&mut self, samples: &[f32]) -> Result<(), WhisperError> {
+        if samples.is_empty() {
+            return Err(WhisperError::NoSamples);
+        }
+        let ok = unsafe {
+            whisper_rs_sys::whisper_vad_detect_speech(
+                self.ptr,
+                samples.as_ptr(),
+                samples.len() as c_int,
+            )
+        };
+        if ok {
+            Ok(())
+        } else {
+            Err(WhisperError::FailedToDetectSpeech)
+        }
+    }
+
+    /// Probability of speech for each frame of the audio passed to the last call to
+    /// [WhisperVadContext::detect_speech]. Empty if it has not been called yet.
+    ///
+    /// # C++ equivalent
+    /// `float * whisper_vad_probs(struct whisper_vad_context * vctx);`
+    pub fn probabilities(&self) -> &[f32] {
+        let n_probs = unsafe { whisper_rs_sys::whisper_vad_n_probs(self.ptr) };
+        let probs = unsafe { whisper_rs_sys::whisper_vad_probs(self.ptr) };
+        if n_probs <= 0 || probs.is_null() {
+            return &[];
+        }
+        // SAFETY: whisper.cpp owns `n_probs` floats at `probs` until the next detection,
+        // which needs &mut self
+        unsafe { std::slice::from_raw_parts(probs, n_probs as usize) }
+    }
+
+    /// Turn the probabilities from the last call to [WhisperVadContext::detect_speech]
+    /// into speech segments.
+    ///
+    /// # C++ equivalent
+    /// `struct whisper_vad_segments * whisper_vad_segments_from_probs(struct whisper_vad_context * vctx, struct whisper_vad_params params);`
+    pub fn segments_from_probabilities(
+        &mut self,
+        params: WhisperVadParams,
+    ) -> Result<Vec<WhisperVadSegment>, WhisperError> {
+        let segments = unsafe {
+            whisper_rs_sys::whisper_vad_segments_from_probs(self.ptr, params.to_c_struct())
+        };
+        collect_segments(segments)
+    }
+
+    /// Detect speech in `samples` and return the speech segments,
+    /// combining [WhisperVadContext::detect_speech] and
+    /// [WhisperVadContext::segments_from_probabilities].
+    ///
+    /// # C++ equivalent
+    /// `struct whisper_vad_segments * whisper_vad_segments_from_samples(struct whisper_vad_context * vctx, struct whisper_vad_params params, const float * samples, int n_samples);`
+    pub fn segments_from_samples(
+        &mut self,
+        params: WhisperVadParams,
+        samples: &[f32],
+    ) -> Result<Vec<WhisperVadSegment>, WhisperError> {
+        if samples.is_empty() {
+            return Err(WhisperError::NoSamples);
+        }
+        let segments = unsafe {
+            whisper_rs_sys::whisper_vad_segments_from_samples(
+                self.ptr,
+                params.to_c_struct(),
+                samples.as_ptr(),
+                samples.len() as c_int,
+            )
+        };
+        collect_segments(segments)
+    }
+}
+
+/// Copy the segments out of a `whisper_vad_segments` and free it.
+fn collect_segments(
+    segments: *mut whisper_rs_sys::whisper_vad_segments,
+) -> Result<Vec<WhisperVadSegment>, WhisperError> {
+    if segments.is_null() {
+        return Err(WhisperError::FailedToDetectSpeech);
+    }
+    let out = unsafe {
+        let n_segments = whisper_rs_sys::whisper_vad_segments_n_segments(segments);
+        (0..n_segments)
+            .map(|i| WhisperVadSegment {
+                t0: whisper_rs_sys::whisper_vad_segments_get_segment_t0(segments, i),
+                t1: whisper_rs_sys::whisper_vad_segments_get_segment_t1(segments, i),
+            })
+            .collect()
+    };
+    unsafe { whisper_rs_sys::whisper_vad_free_segments(segments) };
+    Ok(out)
+}
+
+impl Drop for WhisperVadContext {
+    fn drop(&mut self) {
+        unsafe { whisper_rs_sys::whisper_vad_free(self.ptr) };
+    }
+}
+
+// the context is only used through &mut self, so it can be moved to another thread,
+// but not shared between them
+unsafe impl Send for WhisperVadContext {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segment_to_speech_span() {
+        let segment = WhisperVadSegment {
+            t0: 150.0,
+            t1: 275.5,
+        };
+        assert_eq!(
+            segment.to_speech_span(),
+            SpeechSpan {
+                start: 24000,
+                end: 44080
+            }
+        );
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "test-with-vad-model")]
+mod test_with_vad_model {
+    use super::*;
+    use crate::test_util::load_sample;
+
+    const VAD_MODEL_PATH: &str = "./sys/whisper.cpp/models/ggml-silero-v5.1.2.bin";
+
+    // These tests expect that the Silero VAD model has been downloaded
+    // using the script `sys/whisper.cpp/models/download-vad-model.sh silero-v5.1.2`
+
+    fn load_vad() -> WhisperVadContext {
+        WhisperVadContext::new(VAD_MODEL_PATH, WhisperVadContextParams::default())
+            .expect("Download the Silero VAD model using 'sys/whisper.cpp/models/download-vad-model.sh silero-v5.1.2'")
+    }
+
+    #[test]
+    fn test_detects_speech_after_silence() {
+        let mut vad = load_vad();
+        let lead = 5 * WHISPER_SAMPLE_RATE as usize;
+        let mut samples = vec![0.0; lead];
+        samples.extend(load_sample());
+
+        let segments = vad
+            .segments_from_samples(WhisperVadParams::default(), &samples)
+            .unwrap();
+        assert!(!segments.is_empty());
+        // the padding may reach back into the silence a bit
+        assert!(segments[0].to_speech_span().start >= lead - WHISPER_SAMPLE_RATE as usize);
+
+        vad.detect_speech(&samples).unwrap();
+        assert!(!vad.probabilities().is_empty());
+        assert!(vad.probabilities().iter().all(|p| (0.0..=1.0).contains(p)));
+        assert_eq!(
+            vad.segments_from_probabilities(WhisperVadParams::default())
+                .unwrap(),
+            segments
+        );
+    }
+
+    #[test]
+    fn test_load_from_buffer() {
+        let buffer = std::fs::read(VAD_MODEL_PATH).unwrap();
+        let mut vad =
+            WhisperVadContext::new_from_buffer(&buffer, WhisperVadContextParams::default())
+                .unwrap();
+        let silence = vec![0.0; WHISPER_SAMPLE_RATE as usize];
+        assert!(vad
+            .segments_from_samples(WhisperVadParams::default(), &silence)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "test-with-tiny-model")]
+    fn test_full_with_vad() {
+        use crate::test_util::MODEL_PATH;
+        use crate::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+        let ctx = WhisperContext::new_with_params(MODEL_PATH, WhisperContextParameters::default())
+            .expect("Download the ggml-tiny.en model using 'sys/whisper.cpp/models/download-ggml-model.sh tiny.en'");
+        let mut state = ctx.create_state().unwrap();
+
+        let lead = 10 * WHISPER_SAMPLE_RATE as usize;
+        let mut samples = vec![0.0; lead];
+        samples.extend(load_sample());
+
+        let mut params = FullParams::new(SamplingStrategy::default());
+        params.set_vad_enable(true);
+        params.set_vad_model_path(Some(VAD_MODEL_PATH));
+        params.set_vad_params(WhisperVadParams::default());
+        state.full(params, &samples).unwrap();
+
+        let transcript = state.transcript().unwrap();
+        assert!(!transcript.text().trim().is_empty());
+        // whisper.cpp maps timestamps back onto the original audio
+        assert!(transcript.segments[0].t0 >= 900);
+    }
+}
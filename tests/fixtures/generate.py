@@ -0,0 +1,162 @@
+#!/usr/bin/env python3
+"""Generate the audio fixtures used by the tests of `src/audio.rs`.
+
+No encoder is needed, the files are written by hand:
+* tone.flac: 0.25 s of stereo 16-bit audio at 8 kHz in verbatim subframes, with the same
+  440 Hz tone and per-channel offsets as the in-memory WAV files of the tests.
+* silence.mp3: 10 MPEG-1 Layer III frames of mono silence at 48 kHz.
+* silence.ogg: Ogg/Vorbis, mono silence at 8 kHz, with a minimal setup header.
+
+Run from this directory: `python3 generate.py`
+"""
+
+import math
+import struct
+
+
+def crc(data, poly, width, init=0):
+    top = 1 << (width - 1)
+    mask = (1 << width) - 1
+    value = init
+    for byte in data:
+        value ^= byte << (width - 8)
+        for _ in range(8):
+            value = ((value << 1) ^ poly) if value & top else (value << 1)
+            value &= mask
+    return value
+
+
+def flac():
+    rate, channels, frames, block = 8000, 2, 2000, 1024
+    samples = []
+    for i in range(frames):
+        t = i / rate
+        samples.append([
+            int((0.25 * math.sin(2 * math.pi * 440 * t) + 0.1 * c) * 32767)
+            for c in range(channels)
+        ])
+
+    out = bytearray(b"fLaC")
+    info = struct.pack(">HH", block, block) + b"\0" * 6
+    info += ((rate << 44) | ((channels - 1) << 41) | (15 << 36) | frames).to_bytes(8, "big")
+    info += b"\0" * 16
+    out += bytes([0x80]) + len(info).to_bytes(3, "big") + info
+
+    for number, start in enumerate(range(0, frames, block)):
+        chunk = samples[start:start + block]
+        # fixed block size, 16-bit block size at the end of the header, rate from STREAMINFO,
+        # independent channels, 16 bits per sample
+        header = bytearray([0xFF, 0xF8, 0x70, ((channels - 1) << 4) | 0x08, number])
+        header += struct.pack(">H", len(chunk) - 1)
+        header.append(crc(header, 0x07, 8))
+        frame = header
+        for c in range(channels):
+            # verbatim subframe
+            frame.append(0x02)
+            frame += b"".join(struct.pack(">h", s[c]) for s in chunk)
+        frame += struct.pack(">H", crc(frame, 0x8005, 16))
+        out += frame
+    return bytes(out)
+
+
+def mp3():
+    # MPEG-1 Layer III, no CRC, 128 kbps, 48 kHz, mono: 384 bytes per frame.
+    # All-zero side information and main data decode to silence.
+    frame = bytes([0xFF, 0xFB, 0x94, 0xC0]) + b"\0" * 380
+    return frame * 10
+
+
+class Bits:
+    """Vorbis packs bits starting from the least significant bit of each byte."""
+
+    def __init__(self):
+        self.data = bytearray()
+        self.used = 0
+
+    def write(self, value, n):
+        for i in range(n):
+            if self.used % 8 == 0:
+                self.data.append(0)
+            self.data[-1] |= ((value >> i) & 1) << (self.used % 8)
+            self.used += 1
+
+    def bytes(self):
+        return bytes(self.data)
+
+
+def vorbis_headers(rate):
+    ident = b"\x01vorbis" + struct.pack("<IBIiiiBB", 0, 1, rate, 0, 0, 0, 0x88, 1)
+    vendor = b"whisper-rs"
+    comment = b"\x03vorbis" + struct.pack("<I", len(vendor)) + vendor + struct.pack("<IB", 0, 1)
+
+    bits = Bits()
+    bits.write(0, 8)  # 1 codebook
+    bits.write(0x564342, 24)
+    bits.write(1, 16)  # dimensions
+    bits.write(2, 24)  # entries
+    bits.write(0, 1)  # not ordered
+    bits.write(0, 1)  # not sparse
+    bits.write(0, 5)  # both entries are 1 bit long
+    bits.write(0, 5)
+    bits.write(0, 4)  # no lookup
+    bits.write(0, 6)  # 1 time domain transform
+    bits.write(0, 16)
+    bits.write(0, 6)  # 1 floor
+    bits.write(1, 16)  # floor type 1
+    bits.write(0, 5)  # no partitions
+    bits.write(0, 2)  # multiplier 1
+    bits.write(7, 4)  # range bits
+    bits.write(0, 6)  # 1 residue
+    bits.write(0, 16)  # residue type 0
+    bits.write(0, 24)  # begin
+    bits.write(0, 24)  # end
+    bits.write(0, 24)  # partition size 1
+    bits.write(0, 6)  # 1 classification
+    bits.write(0, 8)  # classbook
+    bits.write(0, 3)  # no cascade
+    bits.write(0, 1)
+    bits.write(0, 6)  # 1 mapping
+    bits.write(0, 16)  # mapping type 0
+    bits.write(0, 1)  # 1 submap
+    bits.write(0, 1)  # no coupling
+    bits.write(0, 2)  # reserved
+    bits.write(0, 8)  # time
+    bits.write(0, 8)  # floor
+    bits.write(0, 8)  # residue
+    bits.write(0, 6)  # 1 mode
+    bits.write(0, 1)  # short blocks
+    bits.write(0, 16)  # window type
+    bits.write(0, 16)  # transform type
+    bits.write(0, 8)  # mapping
+    bits.write(1, 1)  # framing
+    setup = b"\x05vorbis" + bits.bytes()
+    return ident, comment, setup
+
+
+def ogg_page(packets, granule, sequence, flags):
+    segments = bytearray()
+    for packet in packets:
+        segments += b"\xff" * (len(packet) // 255) + bytes([len(packet) % 255])
+    header = b"OggS" + struct.pack("<BBqIII", 0, flags, granule, 1, sequence, 0)
+    page = bytearray(header + bytes([len(segments)]) + segments + b"".join(packets))
+    page[22:26] = struct.pack("<I", crc(page, 0x04C11DB7, 32))
+    return bytes(page)
+
+
+def ogg():
+    rate, packets = 8000, 41
+    ident, comment, setup = vorbis_headers(rate)
+    # each audio packet is a single 0 bit: an audio packet with an unused floor, i.e. silence.
+    # 256-sample blocks overlap by half, so every packet after the first adds 128 samples.
+    audio = [b"\0"] * packets
+    return (
+        ogg_page([ident], 0, 0, 0x02)
+        + ogg_page([comment, setup], 0, 1, 0)
+        + ogg_page(audio, (packets - 1) * 128, 2, 0x04)
+    )
+
+
+if __name__ == "__main__":
+    for name, data in [("tone.flac", flac()), ("silence.mp3", mp3()), ("silence.ogg", ogg())]:
+        with open(name, "wb") as f:
+            f.write(data)